@@ -0,0 +1,19 @@
+use std::time::Instant;
+
+/// A source of timestamps for time-based cache policies.
+///
+/// Caches read the time through this trait so tests can substitute a clock
+/// they advance by hand.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The wall clock, backed by [`Instant::now`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
@@ -1,2 +1,7 @@
+pub mod clock;
 pub mod lru;
 mod node;
+pub mod sync_lru;
+
+#[cfg(feature = "testing")]
+pub use node::assert_independent;
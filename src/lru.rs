@@ -1,112 +1,3042 @@
-use std::{cell::RefCell, collections::HashMap, hash::Hash, rc::Weak};
+use std::{
+    borrow,
+    cell::{RefCell, RefMut},
+    collections::{hash_map::RandomState, BTreeMap, HashMap},
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter::FusedIterator,
+    mem,
+    rc::{Rc, Weak},
+    time::{Duration, Instant},
+};
 
-use crate::node::{List, Node};
+use crate::{
+    clock::{Clock, SystemClock},
+    node::{List, ListIterator, Node, NodePtr, WeakNodePtr},
+};
 
-pub struct LRU<K: Copy + Eq + Hash, T: Copy> {
-    pub list: List<T>,
-    pub map: HashMap<K, Weak<RefCell<Node<T>>>>,
-    pub capacity: usize,
+const COLD_ACCESS_THRESHOLD: usize = 2;
+
+#[derive(Clone)]
+struct Item<K, T> {
+    key: K,
+    value: T,
+    promoted_at: Instant,
+    accessed_at: Instant,
+    ttl: Option<Duration>,
+    expires_at: Option<Instant>,
+    accesses: usize,
+}
+
+impl<K, T> Item<K, T> {
+    fn new(key: K, value: T, now: Instant) -> Self {
+        Item {
+            key,
+            value,
+            promoted_at: now,
+            accessed_at: now,
+            ttl: None,
+            expires_at: None,
+            accesses: 0,
+        }
+    }
+}
+
+/// Where [`LRU::get_or_insert_with_priority`] places a new entry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InsertPriority {
+    /// The most-recently-used end, like [`LRU::put`].
+    Hot,
+    /// The least-recently-used end, next in line for eviction.
+    Cold,
+    /// The middle of the recency order.
+    Default,
+}
+
+/// Counters reported by [`LRU::stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Calls to [`LRU::get`] that found a live entry.
+    pub hits: usize,
+    /// Calls to [`LRU::get`] that found nothing.
+    pub misses: usize,
+    /// Entries pushed out because the cache was over capacity.
+    pub evictions: usize,
+}
+
+/// An inconsistency between the list and map of an [`LRU`], reported by
+/// [`LRU::validate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError<K> {
+    /// The list's recorded length differs from the number of nodes that are
+    /// reachable from its head, or that the map indexes.
+    ListCountMismatch { expected: usize, actual: usize },
+    /// A map entry whose node is gone or belongs to a different key.
+    StaleMapEntry(K),
+    /// A node whose successor does not link back to it.
+    BrokenListLink { prev_key: K, next_key: K },
+    /// The cache holds more entries than its capacity allows.
+    CapacityExceeded { len: usize, capacity: usize },
+}
+
+impl<K: fmt::Debug> fmt::Display for ValidationError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::ListCountMismatch { expected, actual } => {
+                write!(f, "list holds {} nodes but expected {}", actual, expected)
+            }
+            ValidationError::StaleMapEntry(k) => write!(f, "stale map entry for {:?}", k),
+            ValidationError::BrokenListLink { prev_key, next_key } => {
+                write!(f, "{:?} does not link back to {:?}", next_key, prev_key)
+            }
+            ValidationError::CapacityExceeded { len, capacity } => {
+                write!(f, "{} entries exceed capacity {}", len, capacity)
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug> std::error::Error for ValidationError<K> {}
+
+/// A capacity change rejected by [`LRU::set_capacity`] or [`LRU::resize`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CapacityError {
+    /// A capacity of 0 was requested on a cache that was not built for it,
+    /// see [`LRU::with_zero_capacity_allowed`].
+    Zero,
+}
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CapacityError::Zero => write!(f, "capacity 0 is not allowed on this cache"),
+        }
+    }
+}
+
+impl std::error::Error for CapacityError {}
+
+struct Watermark {
+    ratio: f64,
+    reached: bool,
+    callback: Box<dyn FnMut(usize)>,
 }
 
-impl<K: Copy + Eq + Hash, T: Copy> LRU<K, T> {
+/// A least-recently-used cache.
+///
+/// The backing list and map are private so they cannot drift out of sync;
+/// entries are read through [`LRU::iter`], [`LRU::keys`] and [`LRU::values`].
+///
+/// ```
+/// use lru_cache_rs::lru::LRU;
+///
+/// let mut lru = LRU::with_capacity(2);
+/// lru.put(1, "foo");
+/// lru.put(2, "bar");
+/// assert_eq!(lru.keys().collect::<Vec<_>>(), vec![1, 2]);
+/// ```
+///
+/// ```compile_fail
+/// use lru_cache_rs::lru::LRU;
+///
+/// let mut lru: LRU<i32, &str> = LRU::new();
+/// lru.list.pop_front();
+/// ```
+pub struct LRU<K: Clone + Eq + Hash, T: Clone, S = RandomState> {
+    list: List<Item<K, T>>,
+    map: HashMap<K, WeakNodePtr<Item<K, T>>, S>,
+    capacity: usize,
+    allow_zero_capacity: bool,
+    skip_equal_write: Option<fn(&T, &T) -> bool>,
+    on_evict: Option<Box<dyn FnMut(K, T)>>,
+    high_watermark: Option<Watermark>,
+    clock: Rc<dyn Clock>,
+    idle: Option<Duration>,
+    ttl: Option<Duration>,
+    per_key_ttl: bool,
+    peek_resets_idle: bool,
+    stats: CacheStats,
+}
+
+impl<K: Clone + Eq + Hash, T: Clone> LRU<K, T> {
     pub fn new() -> Self {
         LRU::with_capacity(10)
     }
 
     pub fn with_capacity(capacity: usize) -> Self {
-        LRU {
+        LRU::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher> LRU<K, T, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        LRU::with_capacity_and_hasher(10, hasher)
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        let mut lru = LRU {
             list: List::new(),
-            map: HashMap::new(),
+            map: HashMap::with_hasher(hasher),
             capacity,
+            allow_zero_capacity: capacity == 0,
+            skip_equal_write: None,
+            on_evict: None,
+            high_watermark: None,
+            clock: Rc::new(SystemClock),
+            idle: None,
+            ttl: None,
+            per_key_ttl: false,
+            peek_resets_idle: false,
+            stats: CacheStats::default(),
+        };
+        lru.apply_capacity(capacity)
+            .expect("a zero capacity passed at construction is allowed");
+        lru
+    }
+
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Rc::new(clock);
+        self
+    }
+
+    /// Makes entries expire once they have gone `idle` without being
+    /// accessed. Idle entries are dropped lazily, when a lookup finds them or
+    /// the cache needs their slot, so they still count towards [`LRU::len`]
+    /// until then.
+    pub fn with_tti(mut self, idle: Duration) -> Self {
+        self.idle = Some(idle);
+        self
+    }
+
+    /// Makes entries expire `ttl` after they were last written, however often
+    /// they are read. Like idle entries, expired ones are dropped lazily when
+    /// a lookup finds them.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Controls whether [`LRU::set_capacity`] and [`LRU::resize`] accept a
+    /// capacity of 0. Caches constructed with a capacity of 0 allow it from
+    /// the start; all others reject it until this is turned on.
+    pub fn with_zero_capacity_allowed(mut self, allow: bool) -> Self {
+        self.allow_zero_capacity = allow;
+        self
+    }
+
+    /// Controls whether [`LRU::peek`] counts as an access for time-to-idle
+    /// expiry. Off by default, so peeking leaves the idle timer untouched.
+    pub fn with_peek_resets_idle(mut self, reset: bool) -> Self {
+        self.peek_resets_idle = reset;
+        self
+    }
+
+    pub fn with_evict_callback<F: FnMut(K, T) + 'static>(mut self, f: F) -> Self {
+        self.on_evict = Some(Box::new(f));
+        self
+    }
+
+    pub fn with_high_watermark_callback<F: FnMut(usize) + 'static>(
+        mut self,
+        ratio: f64,
+        f: F,
+    ) -> Self {
+        self.high_watermark = Some(Watermark {
+            ratio,
+            reached: false,
+            callback: Box::new(f),
+        });
+        self.check_high_watermark();
+        self
+    }
+
+    fn check_high_watermark(&mut self) {
+        let len = self.list.len();
+        let capacity = self.capacity;
+        if let Some(watermark) = self.high_watermark.as_mut() {
+            // A zero-capacity cache never fills, so it never crosses the mark.
+            let reached = capacity > 0 && len as f64 >= watermark.ratio * capacity as f64;
+            if reached && !watermark.reached {
+                (watermark.callback)(len);
+            }
+            watermark.reached = reached;
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Every capacity change goes through here so all paths share one rule:
+    /// a capacity of 0 means the cache stores nothing, and is only accepted
+    /// once zero-capacity mode is on.
+    fn apply_capacity(&mut self, capacity: usize) -> Result<(), CapacityError> {
+        if capacity == 0 && !self.allow_zero_capacity {
+            return Err(CapacityError::Zero);
+        }
+
+        self.capacity = capacity;
+        if self.list.len() > self.capacity {
+            self.purge_expired();
+        }
+        while self.list.len() > self.capacity {
+            self.evict();
+        }
+        self.check_high_watermark();
+        Ok(())
+    }
+
+    /// Changes the capacity, evicting least-recently-used entries until the
+    /// cache fits. A capacity of 0 empties the cache and keeps it empty, but
+    /// is rejected with [`CapacityError::Zero`], leaving the cache untouched,
+    /// unless [`LRU::with_zero_capacity_allowed`] is on.
+    pub fn set_capacity(&mut self, new_capacity: usize) -> Result<(), CapacityError> {
+        self.apply_capacity(new_capacity)
+    }
+
+    /// Alias for [`LRU::set_capacity`]: shrinking evicts least-recently-used
+    /// entries until the cache fits, growing leaves the entries untouched.
+    pub fn resize(&mut self, new_capacity: usize) -> Result<(), CapacityError> {
+        self.set_capacity(new_capacity)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over `(key, value)` pairs from the least-recently-used entry
+    /// to the most-recently-used one, without affecting recency.
+    pub fn iter(&self) -> Iter<'_, K, T> {
+        Iter {
+            inner: self.list.iter(),
+        }
+    }
+
+    /// Removes and yields every entry from least- to most-recently-used. The
+    /// cache is left empty when the iterator is dropped, even if it was not
+    /// consumed to the end.
+    pub fn drain(&mut self) -> Drain<'_, K, T, S> {
+        Drain { lru: self }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = K> + '_ {
+        self.list
+            .iter_nodes()
+            .map(|node| node.borrow().value.key.clone())
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = T> + '_ {
+        self.list
+            .iter_nodes()
+            .map(|node| node.borrow().value.value.clone())
+    }
+
+    pub fn count_live_map_entries(&self) -> usize {
+        self.map
+            .values()
+            .filter(|ptr| ptr.upgrade().is_some())
+            .count()
+    }
+
+    /// Restarts `item`'s time-to-live, keeping the duration it was stored
+    /// with.
+    fn restart_ttl(&self, item: &mut Item<K, T>) {
+        item.expires_at = item.ttl.map(|ttl| self.clock.now() + ttl);
+    }
+
+    fn new_item(&self, key: K, value: T, ttl: Option<Duration>) -> Item<K, T> {
+        let mut item = Item::new(key, value, self.clock.now());
+        item.ttl = ttl;
+        self.restart_ttl(&mut item);
+        item
+    }
+
+    fn is_expired(&self, item: &Item<K, T>) -> bool {
+        let now = self.clock.now();
+        item.expires_at.is_some_and(|expires_at| now >= expires_at)
+            || self
+                .idle
+                .is_some_and(|idle| now.duration_since(item.accessed_at) >= idle)
+    }
+
+    /// Checks that the list and map agree, returning the first inconsistency
+    /// found instead of panicking.
+    pub fn validate(&self) -> Result<(), ValidationError<K>> {
+        let mut actual = 0;
+        let mut current = self.list.front_node();
+        while let Some(node) = current {
+            actual += 1;
+            let next = node.borrow().next.clone();
+            if let Some(next) = &next {
+                let linked = next
+                    .borrow()
+                    .prev
+                    .as_ref()
+                    .and_then(|prev| prev.upgrade())
+                    .is_some_and(|prev| Rc::ptr_eq(&prev, &node));
+                if !linked {
+                    return Err(ValidationError::BrokenListLink {
+                        prev_key: node.borrow().value.key.clone(),
+                        next_key: next.borrow().value.key.clone(),
+                    });
+                }
+            }
+            current = next;
+        }
+        if actual != self.list.len() {
+            return Err(ValidationError::ListCountMismatch {
+                expected: self.list.len(),
+                actual,
+            });
         }
+
+        for (k, ptr) in &self.map {
+            let live = ptr
+                .upgrade()
+                .is_some_and(|node| node.borrow().value.key == *k);
+            if !live {
+                return Err(ValidationError::StaleMapEntry(k.clone()));
+            }
+        }
+        if self.map.len() != self.list.len() {
+            return Err(ValidationError::ListCountMismatch {
+                expected: self.list.len(),
+                actual: self.map.len(),
+            });
+        }
+
+        if self.list.len() > self.capacity {
+            return Err(ValidationError::CapacityExceeded {
+                len: self.list.len(),
+                capacity: self.capacity,
+            });
+        }
+        Ok(())
     }
 
-    pub fn get(&mut self, k: K) -> Option<T> {
-        let ptr = self.map.get_mut(&k);
-        if ptr.is_none() {
+    /// Finds the live node for `k`, treating idle and expired entries as absent.
+    fn lookup<Q>(&self, k: &Q) -> Option<NodePtr<Item<K, T>>>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.map.get(k)?.upgrade()?;
+        if self.is_expired(&node.borrow().value) {
             return None;
         }
+        Some(node)
+    }
 
-        let ptr = ptr.unwrap();
-        let ptr = ptr.upgrade();
-        match ptr {
-            None => None,
+    /// Like [`LRU::lookup`], but also drops the entry if it has gone idle or
+    /// expired.
+    fn lookup_mut<Q>(&mut self, k: &Q) -> Option<NodePtr<Item<K, T>>>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.lookup(k);
+        if node.is_none() {
+            if let Some(mut idle) = self.map.get(k).and_then(|ptr| ptr.upgrade()) {
+                self.map.remove(k);
+                self.list.remove_node(&mut idle);
+                self.check_high_watermark();
+            }
+        }
+        node
+    }
+
+    fn promote(&mut self, node: NodePtr<Item<K, T>>) {
+        let now = self.clock.now();
+        {
+            let item = &mut node.borrow_mut().value;
+            item.promoted_at = now;
+            item.accessed_at = now;
+        }
+        self.list.move_node_to_back(node);
+    }
+
+    pub fn get<Q>(&mut self, k: &Q) -> Option<T>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.lookup_mut(k) {
+            None => {
+                self.stats.misses += 1;
+                None
+            }
             Some(node) => {
-                let value = node.borrow().value;
-                self.list.move_node_to_back(node);
+                self.stats.hits += 1;
+                let value = {
+                    let item = &mut node.borrow_mut().value;
+                    item.accesses += 1;
+                    item.value.clone()
+                };
+                self.promote(node);
                 Some(value)
             }
         }
     }
 
-    pub fn put(&mut self, k: K, v: T) {
-        let ptr = self.map.get_mut(&k);
-        let ptr = if ptr.is_some() {
-            ptr.unwrap().upgrade()
+    /// Behaves like [`LRU::get`] when `promote` is set and like [`LRU::peek`]
+    /// otherwise.
+    pub fn get_opt<Q>(&mut self, k: &Q, promote: bool) -> Option<T>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if promote {
+            self.get(k)
         } else {
-            None
+            self.peek(k)
+        }
+    }
+
+    /// Returns the entry's position before it was promoted, counted from the
+    /// least-recently-used end, alongside its value.
+    pub fn get_position_and_value<Q>(&mut self, k: &Q) -> Option<(usize, T)>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let position = self
+            .list
+            .iter_nodes()
+            .position(|node| node.borrow().value.key.borrow() == k)?;
+        let value = self.get(k)?;
+        Some((position, value))
+    }
+
+    pub fn with_mut<Q, R, F: FnOnce(&mut T) -> R>(&mut self, k: &Q, f: F) -> Option<R>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.lookup_mut(k)?;
+        let result = {
+            let item = &mut node.borrow_mut().value;
+            item.accesses += 1;
+            f(&mut item.value)
+        };
+        self.promote(node);
+        Some(result)
+    }
+
+    /// Promotes `k` like [`LRU::get`] and mutably borrows its value. The cache
+    /// stays borrowed until the returned guard is dropped.
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<RefMut<'_, T>>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.lookup_mut(k)?;
+        node.borrow_mut().value.accesses += 1;
+        self.promote(node);
+        let item = self.list.back_mut()?;
+        Some(RefMut::map(item, |item| &mut item.value))
+    }
+
+    /// Returns the value of `k` together with the keys of its colder and warmer
+    /// neighbours, as they were before `k` was promoted.
+    pub fn get_with_sibling_keys<Q>(&mut self, k: &Q) -> Option<(T, Option<K>, Option<K>)>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.lookup_mut(k)?;
+        let (colder, warmer) = {
+            let node = node.borrow();
+            let colder = node
+                .prev
+                .as_ref()
+                .and_then(|prev| prev.upgrade())
+                .map(|prev| prev.borrow().value.key.clone());
+            let warmer = node
+                .next
+                .as_ref()
+                .map(|next| next.borrow().value.key.clone());
+            (colder, warmer)
         };
+        let value = self.get(k)?;
+        Some((value, colder, warmer))
+    }
+
+    pub fn get_with_refresh_interval<Q>(&mut self, k: &Q, interval: Duration) -> Option<T>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.lookup_mut(k)?;
+        let now = self.clock.now();
+        let (value, promoted_at) = {
+            let item = &mut node.borrow_mut().value;
+            item.accesses += 1;
+            item.accessed_at = now;
+            (item.value.clone(), item.promoted_at)
+        };
+
+        if now.duration_since(promoted_at) >= interval {
+            self.promote(node);
+        }
+        Some(value)
+    }
+
+    pub fn refresh<Q>(&mut self, k: &Q) -> bool
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.lookup_mut(k) {
+            None => false,
+            Some(node) => {
+                self.promote(node);
+                true
+            }
+        }
+    }
+
+    pub fn batch_promote<I: IntoIterator<Item = K>>(&mut self, keys: I) {
+        for k in keys {
+            if let Some(node) = self.lookup_mut(&k) {
+                self.promote(node);
+            }
+        }
+    }
+
+    pub fn batch_demote<I: IntoIterator<Item = K>>(&mut self, keys: I) {
+        for k in keys {
+            if let Some(node) = self.lookup_mut(&k) {
+                self.list.move_node_to_front(node);
+            }
+        }
+    }
+
+    /// Rotates the recency order by `n` steps, like [`slice::rotate_left`]: the
+    /// `n` coldest entries become the `n` warmest, keeping their relative
+    /// order. `n` wraps around the length of the cache.
+    pub fn rotate_n(&mut self, n: usize) {
+        let len = self.list.len();
+        if len == 0 {
+            return;
+        }
+        for _ in 0..n % len {
+            if let Some(node) = self.list.front_node() {
+                self.list.move_node_to_back(node);
+            }
+        }
+    }
+
+    /// Ages the coldest `evict_fraction` of the entries without evicting them.
+    ///
+    /// The entries with the fewest accesses are moved to positions
+    /// `0..count`, keeping their relative order, so they are evicted next.
+    /// Ties go to the less recently used entry. `evict_fraction` is clamped to
+    /// `0.0..=1.0`.
+    pub fn apply_aging(&mut self, evict_fraction: f64) {
+        let count = (evict_fraction.clamp(0.0, 1.0) * self.list.len() as f64) as usize;
+        let mut nodes = Vec::with_capacity(self.list.len());
+        let mut current = self.list.front_node();
+        while let Some(node) = current {
+            current = node.borrow().next.clone();
+            nodes.push(node);
+        }
+
+        let mut coldest: Vec<_> = nodes.into_iter().enumerate().collect();
+        coldest.sort_by_key(|(_, node)| node.borrow().value.accesses);
+        coldest.truncate(count);
+        coldest.sort_by_key(|(position, _)| *position);
+        for (_, node) in coldest.into_iter().rev() {
+            self.list.move_node_to_front(node);
+        }
+    }
+
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lookup(k).is_some()
+    }
+
+    pub fn peek<Q>(&self, k: &Q) -> Option<T>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self.lookup(k)?;
+        let item = &mut node.borrow_mut().value;
+        if self.peek_resets_idle {
+            item.accessed_at = self.clock.now();
+        }
+        Some(item.value.clone())
+    }
+
+    /// Inserts or updates `k`, returning the value it displaced: the previous
+    /// value when `k` was already cached, or the evicted least-recently-used
+    /// value when a new key overflows the capacity. Updating an existing key
+    /// never evicts, so at most one value is ever displaced.
+    pub fn put(&mut self, k: K, v: T) -> Option<T> {
+        self.put_expiring(k, v, None)
+    }
+
+    /// Like [`LRU::put`], but the entry expires after `ttl` instead of the
+    /// cache's default.
+    pub fn put_with_ttl(&mut self, k: K, v: T, ttl: Duration) -> Option<T> {
+        self.per_key_ttl = true;
+        self.put_expiring(k, v, Some(ttl))
+    }
+
+    /// Writes `v` under `k`. A new entry expires after `ttl`, or the cache's
+    /// default when `None`; an existing one keeps its own time-to-live unless
+    /// `ttl` overrides it.
+    fn put_expiring(&mut self, k: K, v: T, ttl: Option<Duration>) -> Option<T> {
+        if self.capacity == 0 {
+            return None;
+        }
 
-        match ptr {
+        match self.lookup_mut(&k) {
             None => {
-                self.list.push_back(v);
+                let item = self.new_item(k.clone(), v, ttl.or(self.ttl));
+                self.list.push_back(item);
                 if let Some(tail) = self.list.get_weak_tail() {
                     self.map.insert(k, tail);
                 }
 
                 if self.list.len() > self.capacity {
-                    self.list.pop_front();
+                    self.purge_expired();
                 }
+                let evicted = if self.list.len() > self.capacity {
+                    self.evict()
+                } else {
+                    None
+                };
+                self.check_high_watermark();
+                evicted
             }
             Some(node) => {
-                node.borrow_mut().value = v;
-                self.list.move_node_to_back(node);
+                let unchanged = self
+                    .skip_equal_write
+                    .is_some_and(|eq| eq(&node.borrow().value.value, &v));
+                let old = {
+                    let item = &mut node.borrow_mut().value;
+                    if ttl.is_some() {
+                        item.ttl = ttl;
+                    }
+                    self.restart_ttl(item);
+                    mem::replace(&mut item.value, v)
+                };
+                if !unchanged {
+                    self.promote(node);
+                }
+                Some(old)
             }
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    pub fn entry(&mut self, k: K) -> Entry<'_, K, T, S> {
+        match self.lookup_mut(&k) {
+            Some(node) => Entry::Occupied(OccupiedEntry {
+                lru: self,
+                key: k,
+                node,
+                accessed: false,
+            }),
+            None => Entry::Vacant(VacantEntry { lru: self, key: k }),
+        }
+    }
 
-    #[test]
-    fn works_builds_lru() {
-        let mut lru = LRU::new();
-        lru.put(1, "foo");
-        lru.put(2, "bar");
-        lru.put(3, "fizz");
-        lru.put(4, "buzz");
-        lru.put(5, "bazz");
+    pub fn get_or_insert<F: FnOnce() -> T>(&mut self, k: K, f: F) -> T {
+        self.get_or_insert_with(k, f)
+    }
 
-        assert_eq!(lru.get(3), Some("fizz"));
-        assert_eq!(lru.get(2), Some("bar"));
+    pub fn get_or_try_insert_with<F: FnOnce() -> Option<T>>(&mut self, k: K, f: F) -> Option<T> {
+        if let Some(value) = self.get(&k) {
+            return Some(value);
+        }
 
-        let mut iter = lru.list.iter();
-        assert_eq!(iter.next_back(), Some("bar"));
-        assert_eq!(iter.next_back(), Some("fizz"));
-        assert_eq!(iter.next_back(), Some("bazz"));
-        assert_eq!(iter.next_back(), Some("buzz"));
-        assert_eq!(iter.next_back(), Some("foo"));
-        assert_eq!(iter.next_back(), None);
+        let value = f()?;
+        self.put(k, value.clone());
+        Some(value)
     }
 
-    #[test]
-    fn works_builds_lru_capacity() {
-        let mut lru = LRU::with_capacity(3);
-        lru.put(1, "foo");
-        lru.put(2, "bar");
-        lru.put(3, "fizz");
-        lru.put(4, "buzz");
-        lru.put(5, "bazz");
+    pub fn get_or_insert_with<F: FnOnce() -> T>(&mut self, k: K, f: F) -> T {
+        if let Some(value) = self.get(&k) {
+            return value;
+        }
+
+        let value = f();
+        self.put(k, value.clone());
+        value
+    }
 
-        assert_eq!(lru.get(3), Some("fizz"));
-        assert_eq!(lru.get(4), Some("buzz"));
+    /// Looks `k` up locally and, on a miss, in `upstream`. An upstream hit is
+    /// copied into this cache, so the next lookup is served locally.
+    pub fn get_or_promote_from<S2: BuildHasher>(
+        &mut self,
+        k: K,
+        upstream: &mut LRU<K, T, S2>,
+    ) -> Option<T> {
+        if let Some(value) = self.get(&k) {
+            return Some(value);
+        }
 
-        let mut iter = lru.list.iter();
-        assert_eq!(iter.next_back(), Some("buzz"));
-        assert_eq!(iter.next_back(), Some("fizz"));
-        assert_eq!(iter.next_back(), Some("bazz"));
-        assert_eq!(iter.next_back(), None);
+        let value = upstream.get(&k)?;
+        self.put(k, value.clone());
+        Some(value)
+    }
+
+    /// Like [`LRU::get_or_insert_with`], but a newly inserted entry starts at
+    /// the position chosen by `priority` instead of always being the warmest.
+    pub fn get_or_insert_with_priority<F: FnOnce() -> T>(
+        &mut self,
+        k: K,
+        f: F,
+        priority: InsertPriority,
+    ) -> T {
+        if let Some(value) = self.get(&k) {
+            return value;
+        }
+
+        let position = match priority {
+            InsertPriority::Hot => usize::MAX,
+            InsertPriority::Cold => 0,
+            InsertPriority::Default => self.list.len() / 2,
+        };
+        self.get_or_insert_at_position(k, f(), position)
+    }
+
+    pub fn get_or_insert_at_position(&mut self, k: K, v: T, position: usize) -> T {
+        if let Some(node) = self.lookup_mut(&k) {
+            return node.borrow().value.value.clone();
+        }
+        if self.capacity == 0 {
+            return v;
+        }
+
+        if self.list.len() >= self.capacity {
+            self.purge_expired();
+        }
+        if self.list.len() >= self.capacity {
+            self.evict();
+        }
+
+        let node = Rc::new(RefCell::new(Node::new(self.new_item(
+            k.clone(),
+            v.clone(),
+            self.ttl,
+        ))));
+        self.map.insert(k, Rc::downgrade(&node));
+        self.list.insert_node(position.min(self.list.len()), node);
+        self.check_high_watermark();
+        v
+    }
+
+    fn take_lru(&mut self) -> Option<Item<K, T>> {
+        let item = self.list.pop_front()?;
+        self.map.remove(&item.key);
+        Some(item)
+    }
+
+    fn evict(&mut self) -> Option<T> {
+        let evicted = self.take_lru()?;
+        self.stats.evictions += 1;
+        if let Some(on_evict) = self.on_evict.as_mut() {
+            on_evict(evicted.key, evicted.value.clone());
+        }
+        Some(evicted.value)
+    }
+
+    /// Drops every idle or expired entry so it stops taking up a slot. A
+    /// no-op for caches that can't expire anything.
+    fn purge_expired(&mut self) {
+        if self.idle.is_none() && self.ttl.is_none() && !self.per_key_ttl {
+            return;
+        }
+
+        let mut current = self.list.front_node();
+        while let Some(mut node) = current {
+            current = node.borrow().next.clone();
+            if self.is_expired(&node.borrow().value) {
+                self.map.remove(&node.borrow().value.key);
+                self.list.remove_node(&mut node);
+            }
+        }
+        self.check_high_watermark();
+    }
+
+    /// Removes and returns the least-recently-used live entry, dropping any
+    /// idle or expired entries in front of it.
+    pub fn pop_lru(&mut self) -> Option<(K, T)> {
+        let item = loop {
+            let item = self.take_lru()?;
+            if !self.is_expired(&item) {
+                break item;
+            }
+        };
+        self.check_high_watermark();
+        Some((item.key, item.value))
+    }
+
+    /// Returns the least-recently-used live entry without promoting it.
+    pub fn peek_lru(&self) -> Option<(K, T)> {
+        let mut current = self.list.front_node();
+        while let Some(node) = current {
+            let item = &node.borrow().value;
+            if !self.is_expired(item) {
+                return Some((item.key.clone(), item.value.clone()));
+            }
+            current = node.borrow().next.clone();
+        }
+        None
+    }
+
+    /// Returns the `n`th entry from the least-recently-used end (0 = coldest)
+    /// without promoting it.
+    pub fn peek_nth_lru(&self, n: usize) -> Option<(K, T)> {
+        let item = self.list.nth(n)?;
+        Some((item.key, item.value))
+    }
+
+    /// Returns the `n`th entry from the most-recently-used end (0 = warmest)
+    /// without promoting it.
+    pub fn peek_nth_mru(&self, n: usize) -> Option<(K, T)> {
+        let index = self.list.len().checked_sub(n)?.checked_sub(1)?;
+        self.peek_nth_lru(index)
+    }
+
+    pub fn remove<Q>(&mut self, k: &Q) -> Option<T>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (k, ptr) = self.map.remove_entry(k)?;
+        match ptr.upgrade() {
+            None => None,
+            Some(mut node) => {
+                self.list.remove_node(&mut node);
+                let value = Node::into_value(node).value;
+                if let Some(on_evict) = self.on_evict.as_mut() {
+                    on_evict(k, value.clone());
+                }
+                self.check_high_watermark();
+                Some(value)
+            }
+        }
+    }
+
+    pub fn retain<F: FnMut(&K, &T) -> bool>(&mut self, mut f: F) {
+        let mut current = self.list.front_node();
+        while let Some(mut node) = current {
+            current = node.borrow().next.clone();
+
+            let keep = {
+                let item = &node.borrow().value;
+                f(&item.key, &item.value)
+            };
+            if !keep {
+                self.map.remove(&node.borrow().value.key);
+                self.list.remove_node(&mut node);
+            }
+        }
+        self.check_high_watermark();
+    }
+
+    pub fn clear(&mut self) {
+        self.list.clear();
+        self.map.clear();
+        self.check_high_watermark();
+    }
+
+    /// Rebuilds the list and map from scratch, keeping the current recency
+    /// order. Map entries without a live node are dropped along the way.
+    pub fn normalize_positions(&mut self) {
+        let mut list = mem::take(&mut self.list);
+        self.map.clear();
+        while let Some(item) = list.pop_front() {
+            let key = item.key.clone();
+            self.list.push_back(item);
+            if let Some(tail) = self.list.get_weak_tail() {
+                self.map.insert(key, tail);
+            }
+        }
+    }
+
+    pub fn expire<Q>(&mut self, k: &Q) -> Option<T>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove(k)
+    }
+
+    pub fn into_pairs_sorted_by_recency(mut self) -> Vec<(K, T)> {
+        let mut pairs = Vec::with_capacity(self.list.len());
+        while let Some(item) = self.list.pop_back() {
+            pairs.push((item.key, item.value));
+        }
+        pairs
+    }
+
+    /// Consumes the cache into a map keyed by position, counted from the
+    /// least-recently-used end (0 = coldest).
+    pub fn into_ranked(mut self) -> BTreeMap<usize, (K, T)> {
+        let mut ranked = BTreeMap::new();
+        let mut rank = 0;
+        while let Some(item) = self.list.pop_front() {
+            ranked.insert(rank, (item.key, item.value));
+            rank += 1;
+        }
+        ranked
+    }
+
+    pub fn lru_batch(&self, n: usize) -> Vec<(K, T)> {
+        self.list
+            .iter()
+            .filter(|item| !self.is_expired(item))
+            .take(n)
+            .map(|item| (item.key, item.value))
+            .collect()
+    }
+
+    /// Returns the entries colder than position `pos`, least-recently-used
+    /// first. Positions count from the least-recently-used end, so these are
+    /// the entries at positions `0..pos`, the next in line for eviction.
+    pub fn entries_older_than_position(&self, pos: usize) -> Vec<(K, T)> {
+        self.lru_batch(pos)
+    }
+
+    /// Returns every entry with its position, counted from the
+    /// least-recently-used end (0 = coldest).
+    pub fn zip_with_ranks(&self) -> Vec<(K, T, usize)> {
+        self.iter()
+            .enumerate()
+            .map(|(rank, (k, v))| (k, v, rank))
+            .collect()
+    }
+
+    /// Returns the colder half of the cache, least-recently-used first. For
+    /// odd lengths the middle entry belongs to [`LRU::hot_half`].
+    pub fn cold_half(&self) -> Vec<(K, T)> {
+        self.iter().take(self.len() / 2).collect()
+    }
+
+    /// Returns the hotter half of the cache, least-recently-used first.
+    pub fn hot_half(&self) -> Vec<(K, T)> {
+        self.iter().skip(self.len() / 2).collect()
+    }
+
+    pub fn count_cold_entries(&self, n: usize) -> usize {
+        self.list
+            .iter_nodes()
+            .take(n)
+            .filter(|node| node.borrow().value.accesses < COLD_ACCESS_THRESHOLD)
+            .count()
+    }
+
+    pub fn keys_colder_than<Q>(&self, k: &Q) -> Vec<K>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if !self.contains_key(k) {
+            return Vec::new();
+        }
+
+        self.list
+            .iter_nodes()
+            .take_while(|node| node.borrow().value.key.borrow() != k)
+            .map(|node| node.borrow().value.key.clone())
+            .collect()
+    }
+
+    pub fn keys_warmer_than<Q>(&self, k: &Q) -> Vec<K>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if !self.contains_key(k) {
+            return Vec::new();
+        }
+
+        self.list
+            .iter_nodes()
+            .skip_while(|node| node.borrow().value.key.borrow() != k)
+            .skip(1)
+            .map(|node| node.borrow().value.key.clone())
+            .collect()
+    }
+}
+
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher + Default> Default for LRU<K, T, S> {
+    fn default() -> Self {
+        LRU::with_hasher(S::default())
+    }
+}
+
+/// Collects into a cache of the default capacity, so only the last ten pairs
+/// survive. Use [`Extend`] on an [`LRU::with_capacity`] cache for other sizes.
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher + Default> FromIterator<(K, T)>
+    for LRU<K, T, S>
+{
+    fn from_iter<I: IntoIterator<Item = (K, T)>>(iter: I) -> Self {
+        let mut lru = LRU::default();
+        lru.extend(iter);
+        lru
+    }
+}
+
+/// Puts each pair in order, evicting as [`LRU::put`] would.
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher> Extend<(K, T)> for LRU<K, T, S> {
+    fn extend<I: IntoIterator<Item = (K, T)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.put(k, v);
+        }
+    }
+}
+
+/// Deep-copies the entries in recency order, keeping their access metadata.
+/// Callbacks cannot be cloned, so the copy starts without an evict callback or
+/// high watermark; the clock is shared.
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher + Clone> Clone for LRU<K, T, S> {
+    fn clone(&self) -> Self {
+        let mut lru = LRU {
+            list: List::new(),
+            map: HashMap::with_capacity_and_hasher(self.map.len(), self.map.hasher().clone()),
+            capacity: self.capacity,
+            allow_zero_capacity: self.allow_zero_capacity,
+            skip_equal_write: self.skip_equal_write,
+            on_evict: None,
+            high_watermark: None,
+            clock: Rc::clone(&self.clock),
+            idle: self.idle,
+            ttl: self.ttl,
+            per_key_ttl: self.per_key_ttl,
+            peek_resets_idle: self.peek_resets_idle,
+            stats: self.stats,
+        };
+        for item in self.list.iter() {
+            let key = item.key.clone();
+            lru.list.push_back(item);
+            if let Some(tail) = lru.list.get_weak_tail() {
+                lru.map.insert(key, tail);
+            }
+        }
+        lru
+    }
+}
+
+/// Lists the entries from least- to most-recently-used.
+impl<K: Clone + Eq + Hash + fmt::Debug, T: Clone + fmt::Debug, S: BuildHasher> fmt::Debug
+    for LRU<K, T, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LRU")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .field("entries", &self.iter().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<K: Clone + Eq + Hash + Ord, T: Clone + Eq, S: BuildHasher> LRU<K, T, S> {
+    /// Compares the cached pairs of both caches, ignoring recency and the
+    /// order in which their maps happen to hash the keys.
+    pub fn same_contents(&self, other: &Self) -> bool {
+        let sorted = |lru: &Self| {
+            let mut pairs: Vec<_> = lru.iter().collect();
+            pairs.sort_by(|a, b| a.0.cmp(&b.0));
+            pairs
+        };
+        self.len() == other.len() && sorted(self) == sorted(other)
+    }
+}
+
+impl<K: Clone + Eq + Hash, U, S: BuildHasher> LRU<K, Weak<U>, S> {
+    /// Like [`LRU::get`], but upgrades the cached `Weak`. An entry whose value
+    /// has been dropped counts as a miss and is removed from the cache.
+    pub fn get_upgraded<Q>(&mut self, k: &Q) -> Option<Rc<U>>
+    where
+        K: borrow::Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some(mut node) = self.lookup(k) {
+            if node.borrow().value.value.strong_count() == 0 {
+                self.map.remove(k);
+                self.list.remove_node(&mut node);
+                self.check_high_watermark();
+            }
+        }
+        self.get(k)?.upgrade()
+    }
+}
+
+impl<K: Clone + Eq + Hash, T: Clone + PartialEq, S: BuildHasher> LRU<K, T, S> {
+    pub fn promote_on_equal_write(&mut self, promote: bool) {
+        self.skip_equal_write = if promote { None } else { Some(T::eq) };
+    }
+}
+
+pub struct Iter<'a, K: Clone, T: Clone> {
+    inner: ListIterator<'a, Item<K, T>>,
+}
+
+impl<K: Clone, T: Clone> Iterator for Iter<'_, K, T> {
+    type Item = (K, T);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|item| (item.key, item.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K: Clone, T: Clone> DoubleEndedIterator for Iter<'_, K, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|item| (item.key, item.value))
+    }
+}
+
+impl<K: Clone, T: Clone> ExactSizeIterator for Iter<'_, K, T> {}
+
+impl<K: Clone, T: Clone> FusedIterator for Iter<'_, K, T> {}
+
+impl<'a, K: Clone + Eq + Hash, T: Clone, S: BuildHasher> IntoIterator for &'a LRU<K, T, S> {
+    type Item = (K, T);
+    type IntoIter = Iter<'a, K, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Drain<'a, K: Clone + Eq + Hash, T: Clone, S: BuildHasher = RandomState> {
+    lru: &'a mut LRU<K, T, S>,
+}
+
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher> Iterator for Drain<'_, K, T, S> {
+    type Item = (K, T);
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lru.pop_lru()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.lru.len(), Some(self.lru.len()))
+    }
+}
+
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher> Drop for Drain<'_, K, T, S> {
+    fn drop(&mut self) {
+        self.lru.clear();
+    }
+}
+
+/// A view into a single cache slot, obtained from [`LRU::entry`].
+pub enum Entry<'a, K: Clone + Eq + Hash, T: Clone, S: BuildHasher = RandomState> {
+    Occupied(OccupiedEntry<'a, K, T, S>),
+    Vacant(VacantEntry<'a, K, T, S>),
+}
+
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher> Entry<'_, K, T, S> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: T) -> T {
+        self.or_insert_with(|| default)
+    }
+
+    /// Returns an occupied entry's value, counting it as an access unless
+    /// [`Entry::and_modify`] already did, or inserts the result of `f`.
+    pub fn or_insert_with<F: FnOnce() -> T>(self, f: F) -> T {
+        match self {
+            Entry::Occupied(entry) if entry.accessed => entry.node.borrow().value.value.clone(),
+            Entry::Occupied(mut entry) => entry.get(),
+            Entry::Vacant(entry) => entry.insert(f()),
+        }
+    }
+
+    /// Applies `f` to an occupied entry's value, promoting it, and leaves a
+    /// vacant entry untouched.
+    pub fn and_modify<F: FnOnce(&mut T)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut entry) => {
+                {
+                    let item = &mut entry.node.borrow_mut().value;
+                    item.accesses += 1;
+                    entry.lru.restart_ttl(item);
+                    f(&mut item.value);
+                }
+                entry.lru.promote(Rc::clone(&entry.node));
+                entry.accessed = true;
+                Entry::Occupied(entry)
+            }
+            Entry::Vacant(entry) => Entry::Vacant(entry),
+        }
+    }
+}
+
+pub struct OccupiedEntry<'a, K: Clone + Eq + Hash, T: Clone, S: BuildHasher = RandomState> {
+    lru: &'a mut LRU<K, T, S>,
+    key: K,
+    node: NodePtr<Item<K, T>>,
+    accessed: bool,
+}
+
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher> OccupiedEntry<'_, K, T, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns a clone of the value and promotes the entry, like [`LRU::get`].
+    pub fn get(&mut self) -> T {
+        let value = {
+            let item = &mut self.node.borrow_mut().value;
+            item.accesses += 1;
+            item.value.clone()
+        };
+        self.lru.promote(Rc::clone(&self.node));
+        value
+    }
+
+    /// Replaces the value, promotes the entry and returns the old value. Like
+    /// [`LRU::put`], the write restarts the entry's time-to-live.
+    pub fn insert(&mut self, v: T) -> T {
+        let old = {
+            let item = &mut self.node.borrow_mut().value;
+            self.lru.restart_ttl(item);
+            mem::replace(&mut item.value, v)
+        };
+        self.lru.promote(Rc::clone(&self.node));
+        old
+    }
+}
+
+pub struct VacantEntry<'a, K: Clone + Eq + Hash, T: Clone, S: BuildHasher = RandomState> {
+    lru: &'a mut LRU<K, T, S>,
+    key: K,
+}
+
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher> VacantEntry<'_, K, T, S> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Inserts `v` as the most-recently-used entry, evicting the
+    /// least-recently-used one if the cache is full.
+    pub fn insert(self, v: T) -> T {
+        self.lru.put(self.key, v.clone());
+        v
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Namespaced<const NS: u8, K>(pub K);
+
+impl<K: Clone + Eq + Hash, T: Clone, S: BuildHasher> LRU<(u8, K), T, S> {
+    pub fn put_ns<const NS: u8>(&mut self, k: Namespaced<NS, K>, v: T) -> Option<T> {
+        self.put((NS, k.0), v)
+    }
+
+    pub fn get_ns<const NS: u8>(&mut self, k: Namespaced<NS, K>) -> Option<T> {
+        self.get(&(NS, k.0))
+    }
+}
+
+/// Serializes the capacity and the entries, least-recently-used first, and
+/// deserializes by replaying [`LRU::put`] so the recency order is rebuilt.
+/// Callbacks, the clock and expiry settings are not part of the snapshot.
+#[cfg(feature = "serde")]
+mod snapshot {
+    use std::hash::{BuildHasher, Hash};
+
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::LRU;
+
+    #[derive(Serialize, Deserialize)]
+    struct Snapshot<K, T> {
+        capacity: usize,
+        entries: Vec<(K, T)>,
+    }
+
+    impl<K, T, S> Serialize for LRU<K, T, S>
+    where
+        K: Clone + Eq + Hash + Serialize,
+        T: Clone + Serialize,
+        S: BuildHasher,
+    {
+        fn serialize<Z: Serializer>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> {
+            Snapshot {
+                capacity: self.capacity(),
+                entries: self.iter().collect(),
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, K, T, S> Deserialize<'de> for LRU<K, T, S>
+    where
+        K: Clone + Eq + Hash + Deserialize<'de>,
+        T: Clone + Deserialize<'de>,
+        S: BuildHasher + Default,
+    {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let snapshot = Snapshot::<K, T>::deserialize(deserializer)?;
+            let mut lru = LRU::with_capacity_and_hasher(snapshot.capacity, S::default());
+            lru.extend(snapshot.entries);
+            Ok(lru)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell, collections::hash_map::DefaultHasher, hash::BuildHasherDefault, rc::Weak,
+        thread,
+    };
+
+    use super::*;
+
+    #[test]
+    fn works_builds_lru() {
+        let mut lru = LRU::new();
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.put(4, "buzz");
+        lru.put(5, "bazz");
+
+        assert_eq!(lru.get(&3), Some("fizz"));
+        assert_eq!(lru.get(&2), Some("bar"));
+
+        let mut iter = lru.list.iter().map(|item| item.value);
+        assert_eq!(iter.next_back(), Some("bar"));
+        assert_eq!(iter.next_back(), Some("fizz"));
+        assert_eq!(iter.next_back(), Some("bazz"));
+        assert_eq!(iter.next_back(), Some("buzz"));
+        assert_eq!(iter.next_back(), Some("foo"));
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn works_caches_strings() {
+        let mut lru = LRU::with_capacity(2);
+        lru.put(1, String::from("foo"));
+        lru.put(2, String::from("bar"));
+        lru.put(1, String::from("fizz"));
+
+        assert_eq!(lru.get(&1), Some(String::from("fizz")));
+        assert_eq!(lru.peek(&2).as_deref(), Some("bar"));
+
+        lru.put(3, String::from("buzz"));
+        assert_eq!(lru.get(&2), None);
+        assert_eq!(lru.remove(&3), Some(String::from("buzz")));
+        assert_eq!(lru.len(), 1);
+    }
+
+    #[test]
+    fn works_caches_string_keys() {
+        let mut lru = LRU::with_capacity(2);
+        lru.put(String::from("foo"), 1);
+        lru.put(String::from("bar"), 2);
+        lru.put(String::from("fizz"), 3);
+
+        assert!(!lru.contains_key("foo"));
+        assert_eq!(lru.get("bar"), Some(2));
+        assert_eq!(lru.remove("fizz"), Some(3));
+        assert_eq!(lru.keys_colder_than("bar"), Vec::<String>::new());
+        assert_eq!(lru.len(), 1);
+        assert_eq!(lru.map.len(), 1);
+    }
+
+    #[test]
+    fn works_separates_namespaces() {
+        let mut lru = LRU::with_capacity(4);
+        lru.put_ns(Namespaced::<0, _>(1), "foo");
+        lru.put_ns(Namespaced::<1, _>(1), "bar");
+
+        assert_eq!(lru.get_ns(Namespaced::<0, _>(1)), Some("foo"));
+        assert_eq!(lru.get_ns(Namespaced::<1, _>(1)), Some("bar"));
+        assert_eq!(lru.get_ns(Namespaced::<2, _>(1)), None);
+        assert_eq!(lru.len(), 2);
+    }
+
+    #[test]
+    fn works_moves_values_out() {
+        let mut lru = LRU::with_capacity(1);
+        lru.put(1, vec![String::from("foo")]);
+        let value = lru.remove(&1).unwrap();
+        assert_eq!(value, vec![String::from("foo")]);
+
+        lru.put(2, vec![String::from("bar")]);
+        let pairs = lru.into_pairs_sorted_by_recency();
+        assert_eq!(pairs, vec![(2, vec![String::from("bar")])]);
+    }
+
+    #[test]
+    fn works_builds_lru_capacity() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.put(4, "buzz");
+        lru.put(5, "bazz");
+
+        assert_eq!(lru.get(&3), Some("fizz"));
+        assert_eq!(lru.get(&4), Some("buzz"));
+
+        let mut iter = lru.list.iter().map(|item| item.value);
+        assert_eq!(iter.next_back(), Some("buzz"));
+        assert_eq!(iter.next_back(), Some("fizz"));
+        assert_eq!(iter.next_back(), Some("bazz"));
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn works_reports_len() {
+        let mut lru = LRU::with_capacity(3);
+        assert!(lru.is_empty());
+
+        for i in 0..5 {
+            lru.put(i, "foo");
+        }
+        assert_eq!(lru.len(), 3);
+        assert!(!lru.is_empty());
+
+        lru.remove(&4);
+        assert_eq!(lru.len(), 2);
+    }
+
+    #[test]
+    fn works_reports_capacity() {
+        let lru: LRU<i32, &str> = LRU::new();
+        assert_eq!(lru.capacity(), 10);
+        assert!(lru.is_empty());
+
+        let mut lru = LRU::with_capacity(2);
+        lru.put(1, "foo");
+        assert_eq!(lru.len(), 1);
+        lru.put(2, "bar");
+        assert_eq!(lru.len(), 2);
+        lru.put(3, "fizz");
+        assert_eq!(lru.len(), lru.capacity());
+    }
+
+    #[test]
+    fn works_iterates_in_lru_order() {
+        let mut lru = LRU::with_capacity(5);
+        for i in 1..=5 {
+            lru.put(i, i * 10);
+        }
+        lru.get(&2);
+        lru.get(&4);
+
+        let pairs: Vec<(i32, i32)> = lru.iter().collect();
+        assert_eq!(pairs, vec![(1, 10), (3, 30), (5, 50), (2, 20), (4, 40)]);
+
+        let mut keys = Vec::new();
+        for (k, _) in &lru {
+            keys.push(k);
+        }
+        assert_eq!(keys, vec![1, 3, 5, 2, 4]);
+        assert_eq!(lru.iter().next_back(), Some((4, 40)));
+    }
+
+    #[test]
+    fn works_sets_capacity() {
+        let mut lru = LRU::with_capacity(5);
+        for i in 1..=5 {
+            lru.put(i, i * 10);
+        }
+        lru.get(&1);
+
+        assert_eq!(lru.set_capacity(8), Ok(()));
+        assert_eq!(lru.len(), 5);
+
+        assert_eq!(lru.set_capacity(3), Ok(()));
+        assert_eq!(lru.capacity(), 3);
+        assert_eq!(
+            lru.iter().collect::<Vec<_>>(),
+            vec![(4, 40), (5, 50), (1, 10)]
+        );
+        assert_eq!(lru.map.len(), 3);
+
+        lru.put(6, 60);
+        assert_eq!(lru.len(), 3);
+        assert!(!lru.contains_key(&4));
+    }
+
+    #[test]
+    fn works_iterates_small_cache() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put("a", 1);
+        lru.put("b", 2);
+        lru.put("c", 3);
+        lru.put("a", 4);
+        lru.peek(&"b");
+
+        let pairs: Vec<_> = lru.iter().collect();
+        assert_eq!(pairs, vec![("b", 2), ("c", 3), ("a", 4)]);
+    }
+
+    #[test]
+    fn works_lists_keys_and_values() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.get(&1);
+
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 3, 1]);
+        assert_eq!(
+            lru.values().rev().collect::<Vec<_>>(),
+            vec!["foo", "fizz", "bar"]
+        );
+    }
+
+    #[test]
+    fn works_peeks_without_promoting() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+
+        assert_eq!(lru.peek(&1), Some("foo"));
+        assert_eq!(lru.peek(&3), None);
+
+        let mut iter = lru.list.iter().map(|item| item.value);
+        assert_eq!(iter.next(), Some("foo"));
+        assert_eq!(iter.next(), Some("bar"));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn works_contains_key() {
+        let mut lru = LRU::with_capacity(2);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+
+        assert!(lru.contains_key(&1));
+        assert!(!lru.contains_key(&3));
+
+        lru.put(3, "fizz");
+        assert!(lru.contains_key(&2));
+        assert!(!lru.contains_key(&1));
+    }
+
+    #[test]
+    fn works_contains_key_ignores_dangling_entry() {
+        let mut lru: LRU<i32, &str> = LRU::with_capacity(2);
+        lru.map.insert(1, Weak::new());
+
+        assert!(!lru.contains_key(&1));
+        assert_eq!(lru.peek(&1), None);
+    }
+
+    #[test]
+    fn works_gets_position_and_value() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+
+        assert_eq!(lru.get_position_and_value(&2), Some((1, "bar")));
+        assert_eq!(lru.get_position_and_value(&2), Some((2, "bar")));
+        assert_eq!(lru.get_position_and_value(&1), Some((0, "foo")));
+        assert_eq!(lru.get_position_and_value(&4), None);
+        assert_eq!(lru.keys_colder_than(&1), vec![3, 2]);
+    }
+
+    #[test]
+    fn works_mutates_in_place() {
+        let mut lru = LRU::with_capacity(2);
+        lru.put("a", vec![1]);
+        lru.put("b", vec![2]);
+
+        assert_eq!(
+            lru.with_mut(&"a", |v| {
+                v.push(3);
+                v.len()
+            }),
+            Some(2)
+        );
+        assert_eq!(lru.with_mut(&"c", |v| v.push(4)), None);
+        assert_eq!(lru.get(&"a"), Some(vec![1, 3]));
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec!["b", "a"]);
+    }
+
+    #[test]
+    fn works_gets_with_sibling_keys() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+
+        assert_eq!(
+            lru.get_with_sibling_keys(&2),
+            Some(("bar", Some(1), Some(3)))
+        );
+        assert_eq!(lru.get_with_sibling_keys(&1), Some(("foo", None, Some(3))));
+        assert_eq!(lru.get_with_sibling_keys(&1), Some(("foo", Some(2), None)));
+        assert_eq!(lru.get_with_sibling_keys(&4), None);
+    }
+
+    #[test]
+    fn works_gets_with_refresh_interval() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+
+        let interval = Duration::from_secs(60);
+        assert_eq!(lru.get_with_refresh_interval(&1, interval), Some("foo"));
+        assert_eq!(lru.get_with_refresh_interval(&3, interval), None);
+        assert_eq!(lru.keys_colder_than(&2), vec![1]);
+
+        assert_eq!(
+            lru.get_with_refresh_interval(&1, Duration::ZERO),
+            Some("foo")
+        );
+        assert_eq!(lru.keys_colder_than(&1), vec![2]);
+    }
+
+    #[test]
+    fn works_refreshes_entry() {
+        let mut lru = LRU::with_capacity(2);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+
+        assert!(lru.refresh(&1));
+        assert!(!lru.refresh(&3));
+
+        lru.put(3, "fizz");
+        assert_eq!(lru.peek(&1), Some("foo"));
+        assert_eq!(lru.peek(&2), None);
+    }
+
+    #[test]
+    fn works_skips_promotion_on_equal_write() {
+        let mut lru = LRU::with_capacity(3);
+        lru.promote_on_equal_write(false);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(1, "foo");
+
+        let mut iter = lru.list.iter().map(|item| item.key);
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), None);
+
+        lru.put(1, "fizz");
+        let mut iter = lru.list.iter().map(|item| item.key);
+        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), Some(1));
+        assert_eq!(iter.next(), None);
+
+        lru.promote_on_equal_write(true);
+        lru.put(2, "bar");
+        assert_eq!(lru.keys_colder_than(&2), vec![1]);
+    }
+
+    #[test]
+    fn works_peek_keeps_eviction_order() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+
+        assert_eq!(lru.peek(&1), Some("foo"));
+        lru.put(4, "buzz");
+
+        assert_eq!(lru.peek(&1), None);
+        assert_eq!(lru.peek(&2), Some("bar"));
+    }
+
+    #[test]
+    fn works_gets_or_inserts_with() {
+        let mut calls = 0;
+        let mut lru = LRU::with_capacity(2);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+
+        let value = lru.get_or_insert_with(1, || {
+            calls += 1;
+            "fizz"
+        });
+        assert_eq!(value, "foo");
+        assert_eq!(calls, 0);
+        assert_eq!(lru.keys_colder_than(&1), vec![2]);
+
+        let value = lru.get_or_insert_with(3, || {
+            calls += 1;
+            "buzz"
+        });
+        assert_eq!(value, "buzz");
+        assert_eq!(calls, 1);
+        assert_eq!(lru.peek(&3), Some("buzz"));
+        assert!(!lru.contains_key(&2));
+    }
+
+    #[test]
+    fn works_returns_displaced_value() {
+        let mut lru = LRU::with_capacity(2);
+        assert_eq!(lru.put(1, "foo"), None);
+        assert_eq!(lru.put(2, "bar"), None);
+        assert_eq!(lru.put(1, "fizz"), Some("foo"));
+        assert_eq!(lru.put(3, "buzz"), Some("bar"));
+        assert_eq!(lru.len(), 2);
+        assert_eq!(lru.get(&1), Some("fizz"));
+    }
+
+    #[test]
+    fn works_gets_or_inserts() {
+        let mut lru = LRU::with_capacity(2);
+        assert_eq!(lru.get_or_insert(1, || "foo"), "foo");
+        assert_eq!(lru.get_or_insert(1, || "bar"), "foo");
+
+        assert_eq!(lru.get_or_try_insert_with(2, || None), None);
+        assert!(!lru.contains_key(&2));
+        assert_eq!(lru.get_or_try_insert_with(2, || Some("bar")), Some("bar"));
+        assert_eq!(lru.get_or_try_insert_with(2, || None), Some("bar"));
+        assert_eq!(lru.len(), 2);
+    }
+
+    #[test]
+    fn works_inserts_at_position() {
+        let mut lru = LRU::with_capacity(4);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+
+        assert_eq!(lru.get_or_insert_at_position(4, "buzz", 0), "buzz");
+        assert_eq!(lru.get_or_insert_at_position(2, "bazz", 0), "bar");
+        assert_eq!(lru.keys_colder_than(&1), vec![4]);
+
+        assert_eq!(lru.get_or_insert_at_position(5, "jazz", 1), "jazz");
+        assert!(!lru.contains_key(&4));
+        assert_eq!(lru.keys_warmer_than(&1), vec![5, 2, 3]);
+
+        assert_eq!(lru.get_or_insert_at_position(6, "fuzz", 99), "fuzz");
+        assert_eq!(lru.keys_warmer_than(&3), vec![6]);
+        assert_eq!(lru.len(), 4);
+        assert_eq!(lru.map.len(), 4);
+    }
+
+    #[test]
+    fn works_pops_lru() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "a");
+        lru.put(2, "b");
+        lru.get(&1);
+
+        assert_eq!(lru.pop_lru(), Some((2, "b")));
+        assert!(!lru.map.contains_key(&2));
+        assert_eq!(lru.pop_lru(), Some((1, "a")));
+        assert_eq!(lru.pop_lru(), None);
+        assert!(lru.is_empty());
+        assert!(lru.map.is_empty());
+    }
+
+    #[test]
+    fn works_peeks_lru() {
+        let mut lru = LRU::with_capacity(3);
+        assert_eq!(lru.peek_lru(), None);
+
+        lru.put(1, "a");
+        lru.put(2, "b");
+        lru.get(&1);
+
+        assert_eq!(lru.peek_lru(), Some((2, "b")));
+        assert_eq!(lru.peek_lru(), Some((2, "b")));
+        assert_eq!(lru.len(), 2);
+        assert_eq!(lru.pop_lru(), Some((2, "b")));
+    }
+
+    #[test]
+    fn works_batch_promotes() {
+        let mut lru = LRU::with_capacity(4);
+        for i in 1..=4 {
+            lru.put(i, i * 10);
+        }
+
+        lru.batch_promote([2, 5, 1]);
+        assert_eq!(lru.keys_warmer_than(&3), vec![4, 2, 1]);
+        assert_eq!(lru.peek_lru(), Some((3, 30)));
+        assert_eq!(lru.len(), 4);
+    }
+
+    #[test]
+    fn works_batch_demotes() {
+        let mut lru = LRU::with_capacity(4);
+        for i in 1..=4 {
+            lru.put(i, i * 10);
+        }
+
+        lru.batch_demote([3, 5, 4]);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![4, 3, 1, 2]);
+
+        lru.put(5, 50);
+        assert!(!lru.contains_key(&4));
+        assert_eq!(lru.len(), 4);
+    }
+
+    #[test]
+    fn works_removes_entry() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+
+        assert_eq!(lru.remove(&2), Some("bar"));
+        assert_eq!(lru.remove(&2), None);
+        assert_eq!(lru.get(&2), None);
+        assert_eq!(lru.list.len(), 2);
+
+        lru.put(4, "buzz");
+        lru.put(5, "bazz");
+        assert_eq!(lru.list.len(), 3);
+
+        let mut iter = lru.list.iter().map(|item| item.value);
+        assert_eq!(iter.next(), Some("fizz"));
+        assert_eq!(iter.next(), Some("buzz"));
+        assert_eq!(iter.next(), Some("bazz"));
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn works_removes_tail_then_promotes() {
+        let mut lru = LRU::with_capacity(4);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.put(4, "buzz");
+
+        assert_eq!(lru.remove(&4), Some("buzz"));
+        assert_eq!(lru.remove(&1), Some("foo"));
+        assert_eq!(lru.get(&2), Some("bar"));
+        assert_eq!(lru.list.len(), 2);
+
+        let mut iter = lru.list.iter().map(|item| item.value);
+        assert_eq!(iter.next(), Some("fizz"));
+        assert_eq!(iter.next(), Some("bar"));
+        assert_eq!(iter.next(), None);
+
+        assert_eq!(lru.remove(&3), Some("fizz"));
+        assert_eq!(lru.remove(&2), Some("bar"));
+        assert_eq!(lru.list.len(), 0);
+        assert!(lru.list.iter().next().is_none());
+    }
+
+    #[test]
+    fn works_expires_entry() {
+        let mut lru = LRU::with_capacity(2);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+
+        assert_eq!(lru.expire(&1), Some("foo"));
+        assert_eq!(lru.expire(&1), None);
+        assert!(!lru.contains_key(&1));
+        assert_eq!(lru.list.len(), 1);
+    }
+
+    #[test]
+    fn works_retains_entries() {
+        let mut lru = LRU::with_capacity(6);
+        for i in 1..=6 {
+            lru.put(i, i * 10);
+        }
+        lru.get(&1);
+
+        let mut seen = Vec::new();
+        lru.retain(|k, _| {
+            seen.push(*k);
+            k % 3 != 0
+        });
+        assert_eq!(seen, vec![2, 3, 4, 5, 6, 1]);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 4, 5, 1]);
+        assert_eq!(lru.map.len(), 4);
+
+        lru.retain(|_, _| false);
+        assert!(lru.is_empty());
+        assert!(lru.map.is_empty());
+    }
+
+    #[test]
+    fn works_retains_even_values() {
+        let mut lru = LRU::with_capacity(6);
+        for (k, v) in [(1, 2), (2, 3), (3, 5), (4, 4), (5, 7), (6, 6)] {
+            lru.put(k, v);
+        }
+
+        let mut calls = 0;
+        lru.retain(|_, v| {
+            calls += 1;
+            v % 2 == 0
+        });
+        assert_eq!(calls, 6);
+        assert_eq!(lru.iter().collect::<Vec<_>>(), vec![(1, 2), (4, 4), (6, 6)]);
+        assert_eq!(lru.map.len(), 3);
+        assert_eq!(lru.values().rev().collect::<Vec<_>>(), vec![6, 4, 2]);
+    }
+
+    #[test]
+    fn works_drains_entries() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.get(&1);
+
+        let pairs: Vec<_> = lru.drain().collect();
+        assert_eq!(pairs, vec![(2, "bar"), (3, "fizz"), (1, "foo")]);
+        assert!(lru.is_empty());
+        assert!(lru.map.is_empty());
+
+        lru.put(4, "buzz");
+        assert_eq!(lru.get(&4), Some("buzz"));
+    }
+
+    #[test]
+    fn works_empties_partially_consumed_drain() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        let weak = lru.map[&3].clone();
+
+        let mut drain = lru.drain();
+        assert_eq!(drain.size_hint(), (3, Some(3)));
+        assert_eq!(drain.next(), Some((1, "foo")));
+        drop(drain);
+
+        assert_eq!(lru.len(), 0);
+        assert!(lru.map.is_empty());
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn works_gets_with_optional_promotion() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+
+        assert_eq!(lru.get_opt(&1, false), Some("foo"));
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(lru.get_opt(&1, true), Some("foo"));
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(lru.get_opt(&3, true), None);
+    }
+
+    #[test]
+    fn works_resizes() {
+        let mut lru = LRU::with_capacity(5);
+        for i in 0..5 {
+            lru.put(i, i);
+        }
+
+        assert_eq!(lru.resize(3), Ok(()));
+        assert_eq!(lru.len(), 3);
+        assert_eq!(lru.capacity(), 3);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert!(!lru.map.contains_key(&0));
+        assert!(!lru.map.contains_key(&1));
+
+        assert_eq!(lru.resize(6), Ok(()));
+        assert_eq!(lru.len(), 3);
+    }
+
+    #[test]
+    fn works_stores_nothing_at_zero_capacity() {
+        let mut lru = LRU::with_capacity(0);
+        assert_eq!(lru.put(1, "foo"), None);
+        assert_eq!(lru.get_or_insert_at_position(2, "bar", 0), "bar");
+        assert_eq!(lru.entry(3).or_insert("fizz"), "fizz");
+        assert_eq!(lru.len(), 0);
+        assert!(lru.map.is_empty());
+        assert_eq!(lru.get(&1), None);
+        assert_eq!(lru.get(&2), None);
+        assert_eq!(lru.validate(), Ok(()));
+    }
+
+    #[test]
+    fn works_applies_capacity_rule_on_every_path() {
+        assert_eq!(LRU::<i32, i32>::with_capacity(0).capacity(), 0);
+        assert_eq!(
+            LRU::<i32, i32>::with_capacity_and_hasher(0, RandomState::new()).capacity(),
+            0
+        );
+        assert_eq!(
+            LRU::<i32, i32>::with_capacity(0)
+                .with_tti(Duration::from_secs(1))
+                .capacity(),
+            0
+        );
+
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        assert_eq!(lru.set_capacity(0), Err(CapacityError::Zero));
+        assert_eq!(lru.resize(0), Err(CapacityError::Zero));
+        assert_eq!(lru.capacity(), 3);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![1, 2]);
+
+        let mut lru = lru.with_zero_capacity_allowed(true);
+        assert_eq!(lru.set_capacity(0), Ok(()));
+        assert!(lru.is_empty());
+        assert!(lru.map.is_empty());
+        lru.put(3, "fizz");
+        assert!(lru.is_empty());
+
+        assert_eq!(lru.resize(2), Ok(()));
+        lru.put(3, "fizz");
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![3]);
+        assert_eq!(lru.resize(0), Ok(()));
+        assert_eq!(lru.validate(), Ok(()));
+        assert!(lru.is_empty());
+
+        let mut zero = LRU::<i32, i32>::with_capacity(0);
+        assert_eq!(zero.resize(4), Ok(()));
+        assert_eq!(zero.resize(0), Ok(()));
+    }
+
+    #[test]
+    fn works_applies_aging() {
+        let mut lru = LRU::with_capacity(5);
+        lru.put(0, 0);
+        lru.put(1, 1);
+        for _ in 0..2 {
+            lru.get(&0);
+            lru.get(&1);
+        }
+        lru.put(2, 2);
+        lru.put(3, 3);
+        lru.put(4, 4);
+        lru.get(&4);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+
+        lru.apply_aging(0.4);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 3, 0, 1, 4]);
+        assert_eq!(lru.count_cold_entries(5), 3);
+        assert_eq!(lru.map[&0].upgrade().unwrap().borrow().value.accesses, 2);
+
+        lru.apply_aging(0.0);
+        lru.apply_aging(2.0);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 3, 0, 1, 4]);
+        assert_eq!(lru.validate(), Ok(()));
+    }
+
+    #[test]
+    fn works_tracks_stats() {
+        let mut lru = LRU::with_capacity(2);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.get(&1);
+        lru.get(&1);
+        lru.get(&3);
+        lru.peek(&3);
+        lru.contains_key(&4);
+        lru.put(3, "fizz");
+
+        assert_eq!(
+            lru.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 1,
+                evictions: 1
+            }
+        );
+
+        lru.reset_stats();
+        assert_eq!(lru.stats(), CacheStats::default());
+        lru.get(&2);
+        assert_eq!(lru.stats().misses, 1);
+    }
+
+    #[test]
+    fn works_validates() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        assert_eq!(lru.validate(), Ok(()));
+
+        lru.map.insert(4, Weak::new());
+        assert_eq!(lru.validate(), Err(ValidationError::StaleMapEntry(4)));
+        lru.map.remove(&4);
+
+        let ptr = lru.map.remove(&1).unwrap();
+        assert_eq!(
+            lru.validate(),
+            Err(ValidationError::ListCountMismatch {
+                expected: 3,
+                actual: 2
+            })
+        );
+        lru.map.insert(1, ptr);
+
+        lru.capacity = 2;
+        assert_eq!(
+            lru.validate(),
+            Err(ValidationError::CapacityExceeded {
+                len: 3,
+                capacity: 2
+            })
+        );
+        lru.capacity = 3;
+
+        let second = lru.map[&2].upgrade().unwrap();
+        second.borrow_mut().prev = None;
+        assert_eq!(
+            lru.validate(),
+            Err(ValidationError::BrokenListLink {
+                prev_key: 1,
+                next_key: 2
+            })
+        );
+    }
+
+    #[test]
+    fn works_normalizes_positions() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.get(&1);
+        lru.map.insert(4, Weak::new());
+        let weak = lru.map[&2].clone();
+
+        lru.normalize_positions();
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 3, 1]);
+        assert_eq!(lru.map.len(), 3);
+        assert_eq!(lru.count_live_map_entries(), 3);
+        assert!(weak.upgrade().is_none());
+        assert_eq!(lru.get(&2), Some("bar"));
+    }
+
+    #[test]
+    fn works_reuses_cleared_cache() {
+        let mut lru = LRU::with_capacity(10);
+        for i in 0..10 {
+            lru.put(i, i);
+        }
+
+        lru.clear();
+        for i in 10..15 {
+            lru.put(i, i);
+        }
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![10, 11, 12, 13, 14]);
+        assert_eq!(lru.get(&3), None);
+        assert_eq!(lru.capacity(), 10);
+    }
+
+    #[test]
+    fn works_clears_cache() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        let weak = lru.map[&1].clone();
+
+        lru.clear();
+        assert_eq!(lru.len(), 0);
+        assert!(lru.map.is_empty());
+        assert!(weak.upgrade().is_none());
+        assert_eq!(lru.get(&1), None);
+        assert_eq!(lru.capacity(), 3);
+
+        lru.put(4, "buzz");
+        lru.put(5, "bazz");
+        lru.put(6, "fuzz");
+        lru.put(7, "jazz");
+        assert_eq!(lru.len(), 3);
+        assert_eq!(lru.get(&4), None);
+        assert_eq!(lru.get(&7), Some("jazz"));
+    }
+
+    #[test]
+    fn works_converts_into_pairs_by_recency() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.get(&1);
+
+        assert_eq!(
+            lru.into_pairs_sorted_by_recency(),
+            vec![(1, "foo"), (3, "fizz"), (2, "bar")]
+        );
+        assert!(LRU::<i32, &str>::new()
+            .into_pairs_sorted_by_recency()
+            .is_empty());
+    }
+
+    #[test]
+    fn works_lists_lru_batch() {
+        let mut lru = LRU::with_capacity(5);
+        for i in 1..=5 {
+            lru.put(i, i * 10);
+        }
+        lru.get(&1);
+        lru.get(&3);
+
+        assert_eq!(lru.lru_batch(3), vec![(2, 20), (4, 40), (5, 50)]);
+        assert_eq!(lru.lru_batch(10).len(), 5);
+        assert!(lru.lru_batch(0).is_empty());
+        assert_eq!(lru.keys_colder_than(&1), vec![2, 4, 5]);
+    }
+
+    #[test]
+    fn works_counts_cold_entries() {
+        let mut lru = LRU::with_capacity(4);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.put(4, "buzz");
+
+        lru.get(&2);
+        lru.get(&2);
+        lru.get(&3);
+        lru.peek(&1);
+        lru.peek(&1);
+
+        assert_eq!(lru.count_cold_entries(2), 2);
+        assert_eq!(lru.count_cold_entries(4), 3);
+        assert_eq!(lru.count_cold_entries(10), 3);
+        assert_eq!(lru.count_cold_entries(0), 0);
+    }
+
+    #[test]
+    fn works_lists_colder_keys() {
+        let mut lru = LRU::with_capacity(4);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.put(4, "buzz");
+        lru.get(&2);
+
+        assert_eq!(lru.keys_colder_than(&4), vec![1, 3]);
+        assert_eq!(lru.keys_colder_than(&2), vec![1, 3, 4]);
+        assert_eq!(lru.keys_colder_than(&1), Vec::<i32>::new());
+        assert_eq!(lru.keys_colder_than(&5), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn works_lists_warmer_keys() {
+        let mut lru = LRU::with_capacity(4);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.put(4, "buzz");
+        lru.get(&2);
+
+        assert_eq!(lru.keys_warmer_than(&1), vec![3, 4, 2]);
+        assert_eq!(lru.keys_warmer_than(&4), vec![2]);
+        assert_eq!(lru.keys_warmer_than(&2), Vec::<i32>::new());
+        assert_eq!(lru.keys_warmer_than(&5), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn works_queries_keys_without_cloning_values() {
+        struct Counted(Rc<Cell<usize>>);
+
+        impl Clone for Counted {
+            fn clone(&self) -> Self {
+                self.0.set(self.0.get() + 1);
+                Counted(Rc::clone(&self.0))
+            }
+        }
+
+        let clones = Rc::new(Cell::new(0));
+        let mut lru = LRU::with_capacity(3);
+        for i in 0..3 {
+            lru.put(i, Counted(Rc::clone(&clones)));
+        }
+
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(lru.keys_colder_than(&1), vec![0]);
+        assert_eq!(lru.keys_warmer_than(&1), vec![2]);
+        assert_eq!(lru.count_cold_entries(3), 3);
+        assert_eq!(clones.get(), 0);
+    }
+
+    #[test]
+    fn works_calls_evict_callback() {
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        let sink = evicted.clone();
+        let mut lru =
+            LRU::with_capacity(2).with_evict_callback(move |k, v| sink.borrow_mut().push((k, v)));
+
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(1, "fizz");
+        assert!(evicted.borrow().is_empty());
+
+        lru.put(3, "buzz");
+        assert_eq!(lru.remove(&1), Some("fizz"));
+        assert_eq!(lru.remove(&1), None);
+        assert_eq!(*evicted.borrow(), vec![(2, "bar"), (1, "fizz")]);
+    }
+
+    #[test]
+    fn works_calls_evict_callback_in_eviction_order() {
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        let sink = evicted.clone();
+        let mut lru =
+            LRU::with_capacity(2).with_evict_callback(move |k, v| sink.borrow_mut().push((k, v)));
+
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(2, "fizz");
+        lru.put(3, "buzz");
+        lru.put(4, "bazz");
+
+        assert_eq!(*evicted.borrow(), vec![(1, "foo"), (2, "fizz")]);
+    }
+
+    #[test]
+    fn works_calls_high_watermark_callback_once() {
+        let crossings = Rc::new(RefCell::new(Vec::new()));
+        let sink = crossings.clone();
+        let mut lru = LRU::with_capacity(10)
+            .with_high_watermark_callback(0.9, move |len| sink.borrow_mut().push(len));
+
+        for i in 0..8 {
+            lru.put(i, "foo");
+        }
+        assert!(crossings.borrow().is_empty());
+
+        lru.put(8, "foo");
+        assert_eq!(*crossings.borrow(), vec![9]);
+
+        for i in 9..20 {
+            lru.put(i, "foo");
+        }
+        assert_eq!(*crossings.borrow(), vec![9]);
+
+        lru.remove(&19);
+        lru.remove(&18);
+        lru.put(18, "foo");
+        assert_eq!(*crossings.borrow(), vec![9, 9]);
+    }
+
+    #[test]
+    fn works_skips_high_watermark_at_zero_capacity() {
+        let crossings = Rc::new(Cell::new(0));
+        let sink = crossings.clone();
+        let mut lru = LRU::with_capacity(0)
+            .with_high_watermark_callback(0.9, move |_| sink.set(sink.get() + 1));
+        lru.put(1, "foo");
+        assert_eq!(crossings.get(), 0);
+
+        assert_eq!(lru.set_capacity(1), Ok(()));
+        lru.put(1, "foo");
+        assert_eq!(crossings.get(), 1);
+        assert_eq!(lru.set_capacity(0), Ok(()));
+        assert_eq!(crossings.get(), 1);
+    }
+
+    #[test]
+    fn works_bounds_map_to_capacity() {
+        let mut lru = LRU::new();
+        for i in 0..10_000 {
+            lru.put(i, i);
+        }
+
+        assert_eq!(lru.map.len(), 10);
+        assert!(lru.map.values().all(|ptr| ptr.upgrade().is_some()));
+    }
+
+    #[test]
+    fn works_counts_live_map_entries() {
+        let mut lru = LRU::with_capacity(3);
+        for i in 0..5 {
+            lru.put(i, "foo");
+        }
+        assert_eq!(lru.count_live_map_entries(), lru.len());
+
+        lru.map.insert(10, Weak::new());
+        assert_eq!(lru.count_live_map_entries(), 3);
+        assert_eq!(lru.map.len(), 4);
+    }
+
+    #[test]
+    fn works_removes_dangling_entry() {
+        let mut lru = LRU::with_capacity(1);
+        lru.put(1, "foo");
+        lru.map.insert(2, Weak::new());
+
+        assert_eq!(lru.remove(&2), None);
+        assert!(!lru.map.contains_key(&2));
+        assert_eq!(lru.remove(&1), Some("foo"));
+        assert_eq!(lru.list.len(), 0);
+    }
+
+    #[test]
+    fn works_evicts_map_entry() {
+        let mut lru = LRU::with_capacity(3);
+        for i in 0..100 {
+            lru.put(i, i * 2);
+        }
+
+        assert_eq!(lru.map.len(), 3);
+        assert_eq!(lru.list.len(), 3);
+        assert!(!lru.map.contains_key(&96));
+        assert_eq!(lru.get(&97), Some(194));
+    }
+
+    #[test]
+    fn works_rotates_order() {
+        let mut lru = LRU::with_capacity(5);
+        for i in 0..5 {
+            lru.put(i, i);
+        }
+
+        lru.rotate_n(2);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 3, 4, 0, 1]);
+        lru.rotate_n(8);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+        assert_eq!(lru.get(&0), Some(0));
+    }
+
+    #[test]
+    fn works_counts_one_access_per_entry_update() {
+        let mut lru = LRU::with_capacity(2);
+        lru.put(1, 10);
+        lru.put(2, 20);
+
+        assert_eq!(lru.entry(1).and_modify(|v| *v += 1).or_insert(0), 11);
+        assert_eq!(lru.map[&1].upgrade().unwrap().borrow().value.accesses, 1);
+        assert_eq!(lru.count_cold_entries(2), 2);
+
+        assert_eq!(lru.entry(2).or_insert(0), 20);
+        assert_eq!(lru.map[&2].upgrade().unwrap().borrow().value.accesses, 1);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![1, 2]);
+    }
+
+    #[test]
+    fn works_inserts_vacant_entry() {
+        let mut lru = LRU::with_capacity(2);
+        lru.put(1, 10);
+        lru.put(2, 20);
+
+        assert_eq!(lru.entry(3).or_insert(0), 0);
+        assert!(!lru.contains_key(&1));
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 3]);
+    }
+
+    #[test]
+    fn works_modifies_occupied_entry() {
+        let mut lru = LRU::with_capacity(2);
+        lru.put(1, 10);
+        lru.put(2, 20);
+
+        assert_eq!(lru.entry(1).and_modify(|v| *v += 1).or_insert(0), 11);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(lru.entry(3).and_modify(|v| *v += 1).or_insert(0), 0);
+        assert_eq!(lru.peek(&1), Some(11));
+    }
+
+    #[test]
+    fn works_formats_debug() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.get(&1);
+
+        assert_eq!(
+            format!("{:?}", lru),
+            r#"LRU { capacity: 3, len: 2, entries: [(2, "bar"), (1, "foo")] }"#
+        );
+    }
+
+    #[test]
+    fn works_lists_entries_older_than_position() {
+        let mut lru = LRU::with_capacity(4);
+        for i in 0..4 {
+            lru.put(i, i * 10);
+        }
+        lru.get(&0);
+
+        assert_eq!(lru.entries_older_than_position(2), vec![(1, 10), (2, 20)]);
+        assert_eq!(lru.entries_older_than_position(0), vec![]);
+        assert_eq!(lru.entries_older_than_position(10).len(), 4);
+    }
+
+    #[test]
+    fn works_into_ranked() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+        lru.get(&1);
+
+        let ranked = lru.into_ranked();
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(ranked[&0], (2, "bar"));
+        assert_eq!(
+            ranked.range(1..).map(|(_, (k, _))| *k).collect::<Vec<_>>(),
+            vec![3, 1]
+        );
+    }
+
+    #[test]
+    fn works_peeks_nth() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.put(3, "fizz");
+
+        assert_eq!(lru.peek_nth_lru(0), Some((1, "foo")));
+        assert_eq!(lru.peek_nth_lru(2), Some((3, "fizz")));
+        assert_eq!(lru.peek_nth_lru(3), None);
+        assert_eq!(lru.peek_nth_mru(0), Some((3, "fizz")));
+        assert_eq!(lru.peek_nth_mru(2), Some((1, "foo")));
+        assert_eq!(lru.peek_nth_mru(3), None);
+        assert_eq!(lru.peek_nth_mru(usize::MAX), None);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn works_zips_with_ranks() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.get(&1);
+
+        assert_eq!(lru.zip_with_ranks(), vec![(2, "bar", 0), (1, "foo", 1)]);
+    }
+
+    #[test]
+    fn works_splits_halves() {
+        let mut lru = LRU::with_capacity(5);
+        for i in 0..5 {
+            lru.put(i, i * 10);
+        }
+
+        assert_eq!(lru.cold_half(), vec![(0, 0), (1, 10)]);
+        assert_eq!(lru.hot_half(), vec![(2, 20), (3, 30), (4, 40)]);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn works_expires_after_ttl() {
+        let mut lru = LRU::with_capacity(3).with_ttl(Duration::from_millis(20));
+        lru.put(1, "foo");
+        lru.put_with_ttl(2, "bar", Duration::from_secs(60));
+        assert_eq!(lru.get(&1), Some("foo"));
+
+        thread::sleep(Duration::from_millis(40));
+        assert_eq!(lru.peek(&1), None);
+        assert_eq!(lru.get(&1), None);
+        assert!(!lru.map.contains_key(&1));
+        assert_eq!(lru.list.len(), 1);
+        assert_eq!(lru.get(&2), Some("bar"));
+    }
+
+    #[test]
+    fn works_upgrades_weak_values() {
+        let foo = Rc::new("foo");
+        let bar = Rc::new("bar");
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, Rc::downgrade(&foo));
+        lru.put(2, Rc::downgrade(&bar));
+
+        assert_eq!(lru.get_upgraded(&1), Some(Rc::clone(&foo)));
+
+        drop(bar);
+        assert_eq!(lru.get_upgraded(&2), None);
+        assert_eq!(lru.len(), 1);
+        assert_eq!(lru.map.len(), 1);
+        assert!(!lru.map.contains_key(&2));
+        assert_eq!(lru.stats().misses, 1);
+        assert_eq!(lru.get_upgraded(&3), None);
+    }
+
+    #[test]
+    fn works_gets_mut() {
+        let mut lru: LRU<i32, u32> = LRU::with_capacity(2);
+        lru.put(1, 10);
+        lru.put(2, 20);
+
+        *lru.get_mut(&1).unwrap() += 5;
+        assert_eq!(lru.get(&1), Some(15));
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![2, 1]);
+        assert!(lru.get_mut(&3).is_none());
+    }
+
+    fn assert_single_entry(lru: &LRU<i32, &str>, k: i32) {
+        assert_eq!(lru.len(), 1);
+        assert_eq!(lru.map.len(), 1);
+        assert_eq!(lru.count_live_map_entries(), 1);
+        assert!(lru.map.contains_key(&k));
+    }
+
+    #[test]
+    fn works_capacity_one_put_and_overwrite() {
+        let mut lru = LRU::with_capacity(1);
+        assert_eq!(lru.put(1, "foo"), None);
+        assert_single_entry(&lru, 1);
+
+        assert_eq!(lru.put(1, "bar"), Some("foo"));
+        assert_single_entry(&lru, 1);
+
+        assert_eq!(lru.put(2, "fizz"), Some("bar"));
+        assert_single_entry(&lru, 2);
+        assert_eq!(lru.get(&1), None);
+    }
+
+    #[test]
+    fn works_capacity_one_repeated_gets() {
+        let mut lru = LRU::with_capacity(1);
+        lru.put(1, "foo");
+        for _ in 0..5 {
+            assert_eq!(lru.get(&1), Some("foo"));
+            assert_single_entry(&lru, 1);
+        }
+        assert_eq!(lru.peek_lru(), Some((1, "foo")));
+    }
+
+    #[test]
+    fn works_capacity_one_remove_and_pop() {
+        let mut lru = LRU::with_capacity(1);
+        lru.put(1, "foo");
+        assert_eq!(lru.remove(&1), Some("foo"));
+        assert!(lru.is_empty());
+        assert!(lru.map.is_empty());
+
+        lru.put(2, "bar");
+        assert_single_entry(&lru, 2);
+        assert_eq!(lru.pop_lru(), Some((2, "bar")));
+        assert!(lru.is_empty());
+        assert!(lru.map.is_empty());
+        assert_eq!(lru.pop_lru(), None);
+
+        lru.put(3, "fizz");
+        assert_single_entry(&lru, 3);
+        assert_eq!(lru.get(&3), Some("fizz"));
+    }
+
+    #[test]
+    fn works_with_custom_hasher() {
+        let mut lru: LRU<i32, &str, BuildHasherDefault<DefaultHasher>> =
+            LRU::with_capacity_and_hasher(2, BuildHasherDefault::default());
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        assert_eq!(lru.get(&1), Some("foo"));
+        assert_eq!(lru.put(3, "fizz"), Some("bar"));
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![1, 3]);
+
+        let copy = lru.clone();
+        assert!(copy.same_contents(&lru));
+        let default: LRU<i32, i32, BuildHasherDefault<DefaultHasher>> = LRU::default();
+        assert_eq!(default.capacity(), 10);
+    }
+
+    #[test]
+    fn works_expires_with_custom_hasher() {
+        let offset = Rc::new(Cell::new(Duration::ZERO));
+        let clock = MockClock {
+            start: Instant::now(),
+            offset: Rc::clone(&offset),
+        };
+        let mut lru: LRU<i32, &str, BuildHasherDefault<DefaultHasher>> =
+            LRU::with_capacity_and_hasher(2, BuildHasherDefault::default())
+                .with_ttl(Duration::from_secs(10))
+                .with_tti(Duration::from_secs(4))
+                .with_clock(clock);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+
+        offset.set(Duration::from_secs(3));
+        assert_eq!(lru.get(&1), Some("foo"));
+        offset.set(Duration::from_secs(5));
+        assert_eq!(lru.get(&2), None);
+        offset.set(Duration::from_secs(6));
+        assert_eq!(lru.get(&1), Some("foo"));
+        offset.set(Duration::from_secs(10));
+        assert_eq!(lru.get(&1), None);
+    }
+
+    #[test]
+    fn works_builds_default() {
+        let mut lru: LRU<i32, i32> = Default::default();
+        assert_eq!(lru.capacity(), LRU::<i32, i32>::new().capacity());
+        assert!(lru.is_empty());
+
+        lru.put(1, 10);
+        assert_eq!(lru.get(&1), Some(10));
+    }
+
+    #[test]
+    fn works_collects_from_iterator() {
+        let lru: LRU<_, _> = (0..12).map(|i| (i, i * 2)).collect();
+        assert_eq!(lru.len(), 10);
+        assert_eq!(lru.peek_lru(), Some((2, 4)));
+
+        let mut lru = LRU::with_capacity(2);
+        lru.extend(vec![(1, "foo"), (2, "bar"), (3, "fizz")]);
+        assert_eq!(
+            lru.iter().collect::<Vec<_>>(),
+            vec![(2, "bar"), (3, "fizz")]
+        );
+    }
+
+    #[test]
+    fn works_compares_contents() {
+        let mut a = LRU::with_capacity(4);
+        let mut b = LRU::with_capacity(8);
+        for i in 0..4 {
+            a.put(i, i * 10);
+            b.put(3 - i, (3 - i) * 10);
+        }
+        a.get(&0);
+        assert!(a.same_contents(&b));
+
+        b.put(2, 0);
+        assert!(!a.same_contents(&b));
+        b.remove(&2);
+        assert!(!a.same_contents(&b));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn works_round_trips_through_serde() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo".to_string());
+        lru.put(2, "bar".to_string());
+        lru.put(3, "fizz".to_string());
+        lru.get(&1);
+
+        let json = serde_json::to_string(&lru).unwrap();
+        assert_eq!(
+            json,
+            r#"{"capacity":3,"entries":[[2,"bar"],[3,"fizz"],[1,"foo"]]}"#
+        );
+
+        let mut restored: LRU<i32, String> = serde_json::from_str(&json).unwrap();
+        assert!(restored.same_contents(&lru));
+        assert_eq!(restored.capacity(), 3);
+        assert_eq!(restored.validate(), Ok(()));
+
+        assert_eq!(restored.put(4, "buzz".to_string()), Some("bar".to_string()));
+        assert_eq!(restored.keys().collect::<Vec<_>>(), vec![3, 1, 4]);
+    }
+
+    #[test]
+    fn works_clones_independently() {
+        let mut lru = LRU::with_capacity(3);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        lru.get(&1);
+
+        let mut copy = lru.clone();
+        assert_eq!(
+            copy.iter().collect::<Vec<_>>(),
+            lru.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(copy.capacity(), 3);
+
+        copy.put(3, "fizz");
+        copy.put(1, "buzz");
+        copy.remove(&2);
+        assert_eq!(copy.keys().collect::<Vec<_>>(), vec![3, 1]);
+        assert_eq!(lru.iter().collect::<Vec<_>>(), vec![(2, "bar"), (1, "foo")]);
+        assert_eq!(lru.count_live_map_entries(), 2);
+    }
+
+    #[test]
+    fn works_inserts_with_priority() {
+        let mut lru = LRU::with_capacity(5);
+        for i in 0..4 {
+            lru.put(i, i);
+        }
+
+        assert_eq!(
+            lru.get_or_insert_with_priority(10, || 10, InsertPriority::Cold),
+            10
+        );
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![10, 0, 1, 2, 3]);
+
+        lru.remove(&10);
+        lru.get_or_insert_with_priority(20, || 20, InsertPriority::Default);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![0, 1, 20, 2, 3]);
+
+        lru.get_or_insert_with_priority(30, || 30, InsertPriority::Hot);
+        assert_eq!(lru.keys().collect::<Vec<_>>(), vec![1, 20, 2, 3, 30]);
+
+        assert_eq!(
+            lru.get_or_insert_with_priority(1, || 99, InsertPriority::Cold),
+            1
+        );
+        assert_eq!(lru.keys().last(), Some(1));
+    }
+
+    #[test]
+    fn works_promotes_from_upstream() {
+        let mut l1 = LRU::with_capacity(2);
+        let mut l2 = LRU::with_capacity(4);
+        l2.put(1, "foo");
+
+        assert_eq!(l1.get_or_promote_from(1, &mut l2), Some("foo"));
+        assert_eq!(l1.peek(&1), Some("foo"));
+        assert_eq!(l1.get_or_promote_from(2, &mut l2), None);
+        assert_eq!(l1.len(), 1);
+    }
+
+    struct MockClock {
+        start: Instant,
+        offset: Rc<Cell<Duration>>,
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            self.start + self.offset.get()
+        }
+    }
+
+    #[test]
+    fn works_expires_idle_entries() {
+        let offset = Rc::new(Cell::new(Duration::ZERO));
+        let clock = MockClock {
+            start: Instant::now(),
+            offset: Rc::clone(&offset),
+        };
+        let mut lru = LRU::with_capacity(3)
+            .with_tti(Duration::from_secs(10))
+            .with_clock(clock);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+
+        offset.set(Duration::from_secs(6));
+        assert_eq!(lru.get(&1), Some("foo"));
+        assert_eq!(lru.peek(&2), Some("bar"));
+
+        offset.set(Duration::from_secs(12));
+        assert_eq!(lru.get(&1), Some("foo"));
+        assert_eq!(lru.peek(&2), None);
+        assert_eq!(lru.get(&2), None);
+        assert!(!lru.map.contains_key(&2));
+        assert_eq!(lru.len(), 1);
+    }
+
+    #[test]
+    fn works_refreshes_ttl_on_entry_writes() {
+        let offset = Rc::new(Cell::new(Duration::ZERO));
+        let clock = MockClock {
+            start: Instant::now(),
+            offset: Rc::clone(&offset),
+        };
+        let mut lru = LRU::with_capacity(3)
+            .with_ttl(Duration::from_secs(10))
+            .with_clock(clock);
+        lru.put(1, 10);
+        lru.put(2, 20);
+
+        offset.set(Duration::from_secs(8));
+        if let Entry::Occupied(mut entry) = lru.entry(1) {
+            assert_eq!(entry.insert(11), 10);
+        }
+        lru.entry(2).and_modify(|v| *v += 1);
+
+        offset.set(Duration::from_secs(12));
+        assert_eq!(lru.get(&1), Some(11));
+        assert_eq!(lru.get(&2), Some(21));
+
+        offset.set(Duration::from_secs(18));
+        assert_eq!(lru.get(&1), None);
+        assert_eq!(lru.get(&2), None);
+    }
+
+    #[test]
+    fn works_skips_expired_entries_at_the_cold_end() {
+        let offset = Rc::new(Cell::new(Duration::ZERO));
+        let clock = MockClock {
+            start: Instant::now(),
+            offset: Rc::clone(&offset),
+        };
+        let mut lru = LRU::with_capacity(3).with_clock(clock);
+        lru.put_with_ttl(1, 10, Duration::from_secs(1));
+        lru.put(2, 20);
+        lru.put_with_ttl(3, 30, Duration::from_secs(1));
+
+        offset.set(Duration::from_secs(2));
+        assert!(!lru.contains_key(&1));
+        assert_eq!(lru.peek_lru(), Some((2, 20)));
+        assert_eq!(lru.lru_batch(3), vec![(2, 20)]);
+
+        assert_eq!(lru.put(4, 40), None);
+        assert_eq!(lru.list.len(), 2);
+        assert_eq!(lru.get(&2), Some(20));
+
+        lru.put_with_ttl(5, 50, Duration::from_secs(1));
+        offset.set(Duration::from_secs(4));
+        assert_eq!(lru.pop_lru(), Some((4, 40)));
+        assert_eq!(lru.pop_lru(), Some((2, 20)));
+        assert_eq!(lru.pop_lru(), None);
+        assert!(lru.map.is_empty());
+    }
+
+    #[test]
+    fn works_keeps_per_key_ttl_on_writes() {
+        let offset = Rc::new(Cell::new(Duration::ZERO));
+        let clock = MockClock {
+            start: Instant::now(),
+            offset: Rc::clone(&offset),
+        };
+        let mut lru = LRU::with_capacity(3)
+            .with_ttl(Duration::from_secs(1))
+            .with_clock(clock);
+        lru.put_with_ttl(1, 10, Duration::from_secs(100));
+        lru.put_with_ttl(2, 20, Duration::from_secs(100));
+        lru.put_with_ttl(3, 30, Duration::from_secs(100));
+
+        lru.entry(1).and_modify(|v| *v += 1);
+        if let Entry::Occupied(mut entry) = lru.entry(2) {
+            entry.insert(21);
+        }
+        lru.put(3, 31);
+
+        offset.set(Duration::from_secs(50));
+        assert_eq!(lru.get(&1), Some(11));
+        assert_eq!(lru.get(&2), Some(21));
+        assert_eq!(lru.get(&3), Some(31));
+
+        lru.put_with_ttl(3, 32, Duration::from_secs(5));
+        offset.set(Duration::from_secs(56));
+        assert_eq!(lru.get(&3), None);
+        assert_eq!(lru.get(&1), Some(11));
+    }
+
+    #[test]
+    fn works_resets_idle_on_peek() {
+        let offset = Rc::new(Cell::new(Duration::ZERO));
+        let clock = MockClock {
+            start: Instant::now(),
+            offset: Rc::clone(&offset),
+        };
+        let mut lru = LRU::with_capacity(3)
+            .with_tti(Duration::from_secs(10))
+            .with_clock(clock)
+            .with_peek_resets_idle(true);
+        lru.put(1, "foo");
+
+        offset.set(Duration::from_secs(6));
+        assert_eq!(lru.peek(&1), Some("foo"));
+        offset.set(Duration::from_secs(12));
+        assert_eq!(lru.get(&1), Some("foo"));
     }
 }
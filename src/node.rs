@@ -1,15 +1,19 @@
 use std::{
-    cell::RefCell,
+    cell::{RefCell, RefMut},
+    collections::VecDeque,
+    fmt,
+    iter::FusedIterator,
+    marker::PhantomData,
     rc::{Rc, Weak},
 };
 
-pub struct Node<T: Copy> {
+pub struct Node<T: Clone> {
     pub value: T,
     pub next: Option<Rc<RefCell<Node<T>>>>,
     pub prev: Option<Weak<RefCell<Node<T>>>>,
 }
 
-impl<T: Copy> Node<T> {
+impl<T: Clone> Node<T> {
     pub fn new(value: T) -> Self {
         Node {
             value,
@@ -17,23 +21,40 @@ impl<T: Copy> Node<T> {
             prev: None,
         }
     }
+
+    pub fn into_value(node: NodePtr<T>) -> T {
+        match Rc::try_unwrap(node) {
+            Ok(node) => node.into_inner().value,
+            Err(node) => node.borrow().value.clone(),
+        }
+    }
+}
+
+/// Prints only the value; following the links would recurse through the list.
+impl<T: Clone + fmt::Debug> fmt::Debug for Node<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Node")
+            .field("value", &self.value)
+            .finish_non_exhaustive()
+    }
 }
 
-impl<T: Copy> From<Node<T>> for Option<Rc<RefCell<Node<T>>>> {
+impl<T: Clone> From<Node<T>> for Option<Rc<RefCell<Node<T>>>> {
     fn from(node: Node<T>) -> Self {
         Some(Rc::new(RefCell::new(node)))
     }
 }
 
-type NodePtr<T> = Rc<RefCell<Node<T>>>;
+pub type NodePtr<T> = Rc<RefCell<Node<T>>>;
+pub type WeakNodePtr<T> = Weak<RefCell<Node<T>>>;
 
-pub struct List<T: Copy> {
+pub struct List<T: Clone> {
     head: Option<NodePtr<T>>,
     tail: Option<NodePtr<T>>,
     count: usize,
 }
 
-impl<T: Copy> List<T> {
+impl<T: Clone> List<T> {
     pub fn new() -> Self {
         List {
             head: None,
@@ -42,6 +63,16 @@ impl<T: Copy> List<T> {
         }
     }
 
+    /// Builds a list of `n` values where value `i` is `f(i)`, like
+    /// [`std::array::from_fn`].
+    pub fn from_fn<F: FnMut(usize) -> T>(n: usize, f: F) -> Self {
+        let mut list = List::new();
+        for value in (0..n).map(f) {
+            list.push_back(value);
+        }
+        list
+    }
+
     pub fn push_front(&mut self, value: T) {
         let mut node = Node::new(value);
 
@@ -54,7 +85,7 @@ impl<T: Copy> List<T> {
                 node.next = Some(current_head.clone());
                 self.head = node.into();
                 if let Some(h) = &self.head {
-                    current_head.borrow_mut().prev = Some(Rc::downgrade(&h));
+                    current_head.borrow_mut().prev = Some(Rc::downgrade(h));
                 }
             }
         };
@@ -80,12 +111,49 @@ impl<T: Copy> List<T> {
         self.count += 1;
     }
 
+    pub fn insert(&mut self, index: usize, value: T) {
+        self.insert_node(index, Rc::new(RefCell::new(Node::new(value))));
+    }
+
+    pub fn insert_node(&mut self, index: usize, node: NodePtr<T>) {
+        assert!(
+            index <= self.count,
+            "insertion index (is {}) should be <= len (is {})",
+            index,
+            self.count
+        );
+
+        if index == 0 {
+            return self.push_node_front(node);
+        }
+        if index == self.count {
+            return self.push_node_back(node);
+        }
+
+        let next = self.node_at(index).expect("index is within bounds");
+        let prev = next
+            .borrow()
+            .prev
+            .as_ref()
+            .and_then(|prev| prev.upgrade())
+            .expect("non-head node has a predecessor");
+
+        {
+            let mut node = node.borrow_mut();
+            node.prev = Some(Rc::downgrade(&prev));
+            node.next = Some(next.clone());
+        }
+
+        next.borrow_mut().prev = Some(Rc::downgrade(&node));
+        prev.borrow_mut().next = Some(node);
+        self.count += 1;
+    }
+
     pub fn pop_back(&mut self) -> Option<T> {
         match self.tail.take() {
             None => None,
             Some(tail) => {
-                let mut tail = tail.borrow_mut();
-                let prev = tail.prev.take();
+                let prev = tail.borrow_mut().prev.take();
                 match prev {
                     None => {
                         self.head.take();
@@ -100,7 +168,7 @@ impl<T: Copy> List<T> {
                 };
 
                 self.count -= 1;
-                Some(tail.value)
+                Some(Node::into_value(tail))
             }
         }
     }
@@ -109,8 +177,7 @@ impl<T: Copy> List<T> {
         match self.head.take() {
             None => None,
             Some(head) => {
-                let mut head = head.borrow_mut();
-                let next = head.next.take();
+                let next = head.borrow_mut().next.take();
                 match next {
                     None => {
                         self.tail.take();
@@ -122,15 +189,60 @@ impl<T: Copy> List<T> {
                 };
 
                 self.count -= 1;
-                Some(head.value)
+                Some(Node::into_value(head))
             }
         }
     }
 
-    pub fn iter(&self) -> ListIterator<T> {
+    pub fn peek_front(&self) -> Option<T> {
+        self.head.as_ref().map(|head| head.borrow().value.clone())
+    }
+
+    /// Returns the value at `index`, counted from the front.
+    pub fn nth(&self, index: usize) -> Option<T> {
+        let node = self.node_at(index)?;
+        let value = node.borrow().value.clone();
+        Some(value)
+    }
+
+    /// Returns the value at `index` together with the values before and after
+    /// it.
+    pub fn neighbors(&self, index: usize) -> Option<(Option<T>, T, Option<T>)> {
+        let node = self.node_at(index)?;
+        let node = node.borrow();
+        let prev = node
+            .prev
+            .as_ref()
+            .and_then(|prev| prev.upgrade())
+            .map(|prev| prev.borrow().value.clone());
+        let next = node.next.as_ref().map(|next| next.borrow().value.clone());
+        Some((prev, node.value.clone(), next))
+    }
+
+    pub fn peek_back(&self) -> Option<T> {
+        self.tail.as_ref().map(|tail| tail.borrow().value.clone())
+    }
+
+    /// Mutably borrows the value at the back of the list.
+    pub fn back_mut(&mut self) -> Option<RefMut<'_, T>> {
+        let tail = self.tail.as_ref()?;
+        Some(RefMut::map(tail.borrow_mut(), |node| &mut node.value))
+    }
+
+    pub fn iter(&self) -> ListIterator<'_, T> {
         ListIterator {
+            nodes: self.iter_nodes(),
+        }
+    }
+
+    /// Iterates over the nodes themselves, so callers can borrow the parts
+    /// of a value they need instead of cloning all of it.
+    pub fn iter_nodes(&self) -> NodeIterator<'_, T> {
+        NodeIterator {
             current: self.head.clone(),
             current_back: self.tail.clone(),
+            remaining: self.count,
+            marker: PhantomData,
         }
     }
 
@@ -163,6 +275,8 @@ impl<T: Copy> List<T> {
                 prev.borrow_mut().next.replace(next);
             }
         }
+
+        self.count -= 1;
     }
 
     pub fn move_node_to_back(&mut self, mut node: NodePtr<T>) {
@@ -170,6 +284,27 @@ impl<T: Copy> List<T> {
         self.push_node_back(node);
     }
 
+    pub fn move_node_to_front(&mut self, mut node: NodePtr<T>) {
+        self.remove_node(&mut node);
+        self.push_node_front(node);
+    }
+
+    pub fn push_node_front(&mut self, node: NodePtr<T>) {
+        match self.head.take() {
+            None => {
+                self.head.replace(node);
+                self.tail = self.head.clone();
+            }
+            Some(current_head) => {
+                current_head.borrow_mut().prev = Some(Rc::downgrade(&node));
+                node.borrow_mut().next.replace(current_head);
+                self.head.replace(node);
+            }
+        }
+
+        self.count += 1;
+    }
+
     pub fn push_node_back(&mut self, node: NodePtr<T>) {
         match self.tail.take() {
             None => {
@@ -182,69 +317,276 @@ impl<T: Copy> List<T> {
                 current_tail.borrow_mut().next = self.tail.clone();
             }
         }
+
+        self.count += 1;
     }
 
-    pub fn get_weak_tail(&self) -> Option<Weak<RefCell<Node<T>>>> {
+    pub fn windows2(&self) -> impl Iterator<Item = (T, T)> + '_ {
+        self.iter().zip(self.iter().skip(1))
+    }
+
+    /// Yields every run of `size` consecutive values, like [`slice::windows`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn windows(&self, size: usize) -> impl Iterator<Item = Vec<T>> {
+        assert!(size != 0, "window size must be non-zero");
+        let values: Vec<T> = self.iter().collect();
+        let count = (values.len() + 1).saturating_sub(size);
+        (0..count).map(move |start| values[start..start + size].to_vec())
+    }
+
+    pub fn front_node(&self) -> Option<NodePtr<T>> {
+        self.head.clone()
+    }
+
+    pub fn get_weak_tail(&self) -> Option<WeakNodePtr<T>> {
         match &self.tail {
             None => None,
             Some(tail) => Some(Rc::downgrade(tail)),
         }
     }
 
+    /// Unlinks every node one at a time, so dropping a long list never
+    /// recurses through the chain of `next` pointers.
+    pub fn clear(&mut self) {
+        while self.pop_back().is_some() {}
+    }
+
     pub fn len(&self) -> usize {
         self.count
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn compact(&mut self) {
+        let mut compacted = List::new();
+        while let Some(value) = self.pop_front() {
+            compacted.push_back(value);
+        }
+        *self = compacted;
+    }
+
+    /// Inserts `sep` between every pair of adjacent values.
+    pub fn intersperse(&mut self, sep: T) {
+        let mut interspersed = List::new();
+        while let Some(value) = self.pop_front() {
+            if !interspersed.is_empty() {
+                interspersed.push_back(sep.clone());
+            }
+            interspersed.push_back(value);
+        }
+        *self = interspersed;
+    }
+
+    pub fn into_reversed(mut self) -> List<T> {
+        let mut reversed = List::new();
+        while let Some(value) = self.pop_front() {
+            reversed.push_front(value);
+        }
+        reversed
+    }
+
+    /// Splits the list into the values matching `pred` and the rest, keeping
+    /// the relative order within each.
+    pub fn partition<F: FnMut(&T) -> bool>(mut self, mut pred: F) -> (List<T>, List<T>) {
+        let mut matching = List::new();
+        let mut rest = List::new();
+        while let Some(value) = self.pop_front() {
+            if pred(&value) {
+                matching.push_back(value);
+            } else {
+                rest.push_back(value);
+            }
+        }
+        (matching, rest)
+    }
+
+    #[cfg(any(test, feature = "testing"))]
+    fn nodes(&self) -> Vec<NodePtr<T>> {
+        let mut nodes = Vec::with_capacity(self.count);
+        let mut current = self.head.clone();
+        while let Some(node) = current {
+            current = node.borrow().next.clone();
+            nodes.push(node);
+        }
+        nodes
+    }
+
+    fn node_at(&self, index: usize) -> Option<NodePtr<T>> {
+        let mut current = self.head.clone();
+        for _ in 0..index {
+            let node = current?;
+            current = node.borrow().next.clone();
+        }
+        current
+    }
+}
+
+impl<T: Clone + Ord> List<T> {
+    pub fn min(&self) -> Option<T> {
+        self.iter().min()
+    }
+
+    pub fn max(&self) -> Option<T> {
+        self.iter().max()
+    }
+}
+
+impl<T: Clone> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        let mut list = List::new();
+        for value in self.iter() {
+            list.push_back(value);
+        }
+        list
+    }
+}
+
+impl<T: Clone> From<List<T>> for VecDeque<T> {
+    fn from(mut list: List<T>) -> Self {
+        let mut deque = VecDeque::with_capacity(list.len());
+        while let Some(value) = list.pop_front() {
+            deque.push_back(value);
+        }
+        deque
+    }
+}
+
+impl<T: Clone> From<VecDeque<T>> for List<T> {
+    fn from(deque: VecDeque<T>) -> Self {
+        let mut list = List::new();
+        for value in deque {
+            list.push_back(value);
+        }
+        list
+    }
+}
+
+impl<T: Clone + fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+pub fn assert_independent<T: Clone>(a: &List<T>, b: &List<T>) {
+    let b_nodes = b.nodes();
+    for (i, x) in a.nodes().iter().enumerate() {
+        for (j, y) in b_nodes.iter().enumerate() {
+            assert!(
+                !Rc::ptr_eq(x, y),
+                "node {} of the first list is node {} of the second",
+                i,
+                j
+            );
+        }
+    }
 }
 
-pub struct ListIterator<T: Copy> {
+pub struct NodeIterator<'a, T: Clone> {
     current: Option<NodePtr<T>>,
     current_back: Option<NodePtr<T>>,
+    remaining: usize,
+    marker: PhantomData<&'a List<T>>,
 }
 
-impl<T: Copy> Iterator for ListIterator<T> {
-    type Item = T;
+impl<T: Clone> Iterator for NodeIterator<'_, T> {
+    type Item = NodePtr<T>;
     fn next(&mut self) -> Option<Self::Item> {
-        let current = self.current.take();
-        if current.is_none() {
+        if self.remaining == 0 {
             return None;
         }
 
-        let current = current.unwrap();
-        let current = current.borrow();
-        self.current = current.next.clone();
-        Some(current.value)
+        let current = self.current.take()?;
+        self.current = current.borrow().next.clone();
+        self.remaining -= 1;
+        Some(current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
-impl<T: Copy> DoubleEndedIterator for ListIterator<T> {
+impl<T: Clone> DoubleEndedIterator for NodeIterator<'_, T> {
     fn next_back(&mut self) -> Option<Self::Item> {
-        let current = self.current_back.take();
-        if current.is_none() {
+        if self.remaining == 0 {
             return None;
         }
 
-        let current = current.unwrap();
-        let current = current.borrow();
-        match &current.prev {
-            None => Some(current.value),
-            Some(prev) => {
-                self.current_back = prev.upgrade();
-                Some(current.value)
-            }
-        }
+        let current = self.current_back.take()?;
+        self.current_back = current
+            .borrow()
+            .prev
+            .as_ref()
+            .and_then(|prev| prev.upgrade());
+        self.remaining -= 1;
+        Some(current)
     }
 }
 
-impl<T: Copy> Drop for List<T> {
+impl<T: Clone> ExactSizeIterator for NodeIterator<'_, T> {}
+
+impl<T: Clone> FusedIterator for NodeIterator<'_, T> {}
+
+pub struct ListIterator<'a, T: Clone> {
+    nodes: NodeIterator<'a, T>,
+}
+
+impl<T: Clone> Iterator for ListIterator<'_, T> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nodes.next().map(|node| node.borrow().value.clone())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.nodes.size_hint()
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for ListIterator<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.nodes
+            .next_back()
+            .map(|node| node.borrow().value.clone())
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for ListIterator<'_, T> {}
+
+impl<T: Clone> FusedIterator for ListIterator<'_, T> {}
+
+impl<T: Clone> Drop for List<T> {
     fn drop(&mut self) {
-        while let Some(_) = self.pop_back() {}
+        self.clear();
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+
     use super::*;
 
+    struct Counted(Rc<Cell<usize>>);
+
+    impl Clone for Counted {
+        fn clone(&self) -> Self {
+            self.0.set(self.0.get() + 1);
+            Counted(self.0.clone())
+        }
+    }
+
     #[test]
     fn works_builds_list() {
         let mut list = List::new();
@@ -283,23 +625,387 @@ mod tests {
         list.push_front(3);
         list.push_front(4);
 
-        let mut idx: usize = 0;
-
-        for (i, j) in list.iter().zip(list.iter().rev()) {
-            println!("Iteration {}: {}, {}", idx, i, j);
-            idx += 1;
-        }
-
         let mut iter = list.iter();
         assert_eq!(iter.next(), Some(4));
         assert_eq!(iter.next_back(), Some(1));
         assert_eq!(iter.next(), Some(3));
         assert_eq!(iter.next_back(), Some(2));
-        assert_eq!(iter.next(), Some(2));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn works_pops_back_single_node() {
+        let mut list = List::new();
+        list.push_back(1);
+        let node = Rc::downgrade(list.head.as_ref().unwrap());
+
+        assert_eq!(list.pop_back(), Some(1));
+        assert_eq!(list.peek_front(), None);
+        assert!(list.head.is_none());
+        assert!(list.tail.is_none());
+        assert_eq!(list.len(), 0);
+        assert!(node.upgrade().is_none());
+    }
+
+    #[test]
+    fn works_pops_without_cloning() {
+        let clones = Rc::new(Cell::new(0));
+        let mut list = List::new();
+        list.push_back(Counted(clones.clone()));
+        list.push_back(Counted(clones.clone()));
+        list.push_back(Counted(clones.clone()));
+
+        assert!(list.pop_front().is_some());
+        assert!(list.pop_back().is_some());
+        assert!(list.pop_back().is_some());
+        assert!(list.pop_back().is_none());
+        assert_eq!(clones.get(), 0);
+    }
+
+    #[test]
+    fn works_peeks_front() {
+        let mut list = List::new();
+        assert_eq!(list.peek_front(), None);
+
+        list.push_back(1);
+        list.push_back(2);
+        assert_eq!(list.peek_front(), Some(1));
+        assert_eq!(list.len(), 2);
+    }
+
+    #[test]
+    fn works_inserts_at_index() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        list.insert(1, 9);
+        assert_eq!(list.len(), 4);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 9, 2, 3]);
+        assert_eq!(list.iter().rev().collect::<Vec<_>>(), vec![3, 2, 9, 1]);
+
+        list.insert(0, 0);
+        list.insert(5, 4);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![0, 1, 9, 2, 3, 4]);
+        assert_eq!(list.pop_back(), Some(4));
+        assert_eq!(list.pop_front(), Some(0));
+        assert_eq!(list.len(), 4);
+    }
+
+    #[test]
+    #[should_panic]
+    fn works_panics_on_insert_out_of_range() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.insert(2, 2);
+    }
+
+    #[test]
+    fn works_finds_min_max() {
+        let mut list = List::new();
+        assert_eq!(list.min(), None);
+        assert_eq!(list.max(), None);
+
+        list.push_back(3);
+        list.push_back(-1);
+        list.push_back(7);
+        list.push_back(2);
+        assert_eq!(list.min(), Some(-1));
+        assert_eq!(list.max(), Some(7));
+    }
+
+    #[test]
+    fn works_clones_list_independently() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+        list.push_back(3);
+
+        let mut clone = list.clone();
+        assert_independent(&list, &clone);
+        assert_eq!(clone.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+
+        clone.pop_front();
+        assert_eq!(list.len(), 3);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn works_detects_shared_nodes() {
+        let mut list = List::new();
+        list.push_back(1);
+
+        let shared = List {
+            head: list.head.clone(),
+            tail: list.tail.clone(),
+            count: 1,
+        };
+        assert_independent(&list, &shared);
+    }
+
+    #[test]
+    fn works_compacts_list() {
+        let mut list = List::new();
+        for i in 0..100 {
+            list.push_back(i);
+            list.push_front(i);
+            if i % 3 == 0 {
+                list.pop_back();
+            }
+        }
+        for _ in 0..20 {
+            list.pop_front();
+        }
+
+        let before: Vec<i32> = list.iter().collect();
+        list.compact();
+        assert_eq!(list.len(), before.len());
+        assert_eq!(list.iter().collect::<Vec<_>>(), before);
+        assert_eq!(
+            list.iter().rev().collect::<Vec<_>>(),
+            before.into_iter().rev().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn works_yields_windows_of_two() {
+        let mut list = List::new();
+        assert_eq!(list.windows2().next(), None);
+
+        list.push_back(1);
+        assert_eq!(list.windows2().next(), None);
+
+        list.push_back(2);
+        list.push_back(3);
+        assert_eq!(list.windows2().collect::<Vec<_>>(), vec![(1, 2), (2, 3)]);
+    }
+
+    #[test]
+    fn works_builds_list_iter_rev() {
+        for size in 0..=3 {
+            let mut list = List::new();
+            for i in 0..size {
+                list.push_back(i);
+            }
+
+            let reversed: Vec<i32> = list.iter().rev().collect();
+            let expected: Vec<i32> = (0..size).rev().collect();
+            assert_eq!(reversed, expected);
+        }
+    }
+
+    #[test]
+    fn works_formats_debug() {
+        let mut list = List::new();
+        list.push_back(1);
+        list.push_back(2);
+
+        assert_eq!(format!("{:?}", list), "[1, 2]");
+        assert_eq!(format!("{:?}", Node::new(1)), "Node { value: 1, .. }");
+    }
+
+    #[test]
+    fn works_partitions() {
+        let mut list = List::new();
+        for i in 1..=4 {
+            list.push_back(i);
+        }
+
+        let (even, odd) = list.partition(|v| v % 2 == 0);
+        assert_eq!(even.iter().collect::<Vec<_>>(), vec![2, 4]);
+        assert_eq!(odd.iter().collect::<Vec<_>>(), vec![1, 3]);
+        assert_eq!(even.len(), 2);
+    }
+
+    #[test]
+    fn works_converts_to_and_from_vec_deque() {
+        let mut list = List::new();
+        for i in 1..=3 {
+            list.push_back(i);
+        }
+
+        let mut deque = VecDeque::from(list);
+        assert_eq!(deque, VecDeque::from([1, 2, 3]));
+        deque.pop_front();
+        deque.push_back(4);
+
+        let list = List::from(deque);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![2, 3, 4]);
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn works_builds_from_fn() {
+        let list = List::from_fn(4, |i| i * i);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![0, 1, 4, 9]);
+        assert_eq!(list.len(), 4);
+        assert_eq!(List::from_fn(0, |i| i).len(), 0);
+    }
+
+    #[test]
+    fn works_builds_default() {
+        let mut list: List<i32> = Default::default();
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.peek_front(), None);
+
+        list.push_back(1);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn works_mutates_back() {
+        let mut list = List::new();
+        assert!(list.back_mut().is_none());
+
+        list.push_back(1);
+        list.push_back(2);
+        *list.back_mut().unwrap() += 10;
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 12]);
+    }
+
+    #[test]
+    fn works_intersperses() {
+        let mut list = List::from_fn(3, |i| i + 1);
+        list.intersperse(0);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![1, 0, 2, 0, 3]);
+        assert_eq!(list.len(), 5);
+
+        let mut single = List::from_fn(1, |i| i);
+        single.intersperse(9);
+        assert_eq!(single.iter().collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn works_windows() {
+        let list = List::from_fn(4, |i| i);
+        assert_eq!(
+            list.windows(3).collect::<Vec<_>>(),
+            vec![vec![0, 1, 2], vec![1, 2, 3]]
+        );
+        assert_eq!(list.windows(4).count(), 1);
+        assert_eq!(list.windows(5).count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "window size must be non-zero")]
+    fn works_windows_panics_on_zero_size() {
+        let _ = List::from_fn(2, |i| i).windows(0);
+    }
+
+    #[test]
+    fn works_alternates_iter_on_odd_length() {
+        let list = List::from_fn(5, |i| i);
+
+        let mut iter = list.iter();
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.next(), Some(0));
         assert_eq!(iter.next_back(), Some(3));
         assert_eq!(iter.next(), Some(1));
-        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.next_back(), Some(2));
         assert_eq!(iter.next(), None);
         assert_eq!(iter.next_back(), None);
+
+        let mut iter = list.iter();
+        assert_eq!(iter.next(), Some(0));
+        assert_eq!(iter.by_ref().rev().collect::<Vec<_>>(), vec![4, 3, 2, 1]);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn works_reports_exact_iter_len() {
+        let list = List::from_fn(3, |i| i);
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 3);
+        iter.next();
+        iter.next_back();
+        assert_eq!(iter.len(), 1);
+        iter.next();
+        assert_eq!(iter.len(), 0);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn works_into_reversed() {
+        let list = List::from_fn(3, |i| i + 1);
+        let head = Rc::downgrade(list.head.as_ref().unwrap());
+
+        let reversed = list.into_reversed();
+        assert_eq!(reversed.iter().collect::<Vec<_>>(), vec![3, 2, 1]);
+        assert_eq!(reversed.iter().rev().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(reversed.len(), 3);
+        assert!(head.upgrade().is_none());
+    }
+
+    #[test]
+    fn works_peeks_both_ends() {
+        let mut list = List::new();
+        assert_eq!(list.peek_back(), None);
+
+        list.push_back(1);
+        assert_eq!(list.peek_front(), Some(1));
+        assert_eq!(list.peek_back(), Some(1));
+
+        list.push_back(2);
+        assert_eq!(list.peek_front(), Some(1));
+        assert_eq!(list.peek_back(), Some(2));
+        assert_eq!(list.len(), 2);
+    }
+
+    #[test]
+    fn works_nth() {
+        let list = List::from_fn(3, |i| i * 10);
+        assert_eq!(list.nth(0), Some(0));
+        assert_eq!(list.nth(2), Some(20));
+        assert_eq!(list.nth(3), None);
+    }
+
+    #[test]
+    fn works_is_empty() {
+        let mut list = List::new();
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+
+        list.push_back(1);
+        list.push_front(0);
+        assert!(!list.is_empty());
+        assert_eq!(list.len(), 2);
+
+        let mut front = list.front_node().unwrap();
+        list.remove_node(&mut front);
+        assert!(!list.is_empty());
+        assert_eq!(list.len(), 1);
+
+        list.pop_back();
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+    }
+
+    #[test]
+    fn works_neighbors() {
+        let list = List::from_fn(3, |i| i + 1);
+        assert_eq!(list.neighbors(1), Some((Some(1), 2, Some(3))));
+        assert_eq!(list.neighbors(0), Some((None, 1, Some(2))));
+        assert_eq!(list.neighbors(2), Some((Some(2), 3, None)));
+        assert_eq!(list.neighbors(3), None);
+    }
+
+    #[test]
+    fn works_clears() {
+        let mut list = List::from_fn(100, |i| i);
+        list.clear();
+        assert_eq!(list.len(), 0);
+        assert!(list.is_empty());
+        assert!(list.head.is_none());
+        assert!(list.tail.is_none());
+
+        list.push_back(1);
+        list.push_front(0);
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![0, 1]);
+        assert_eq!(list.len(), 2);
     }
 }
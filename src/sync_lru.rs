@@ -0,0 +1,220 @@
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+    hash::Hash,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// The cache state behind the mutex. Recency is tracked with a monotonically
+/// increasing tick instead of the `Rc` list `LRU` uses, so the whole thing is
+/// `Send` whenever `K` and `T` are.
+struct Shared<K, T> {
+    capacity: usize,
+    tick: u64,
+    map: HashMap<K, (T, u64)>,
+    order: BTreeMap<u64, K>,
+}
+
+impl<K: Clone + Eq + Hash, T: Clone> Shared<K, T> {
+    fn touch<Q>(&mut self, k: &Q) -> Option<&mut T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (value, tick) = self.map.get_mut(k)?;
+        let key = self.order.remove(tick)?;
+        self.tick += 1;
+        *tick = self.tick;
+        self.order.insert(self.tick, key);
+        Some(value)
+    }
+
+    fn put(&mut self, k: K, v: T) -> Option<T> {
+        if self.capacity == 0 {
+            return None;
+        }
+
+        if let Some(value) = self.touch(&k) {
+            return Some(std::mem::replace(value, v));
+        }
+
+        self.tick += 1;
+        self.order.insert(self.tick, k.clone());
+        self.map.insert(k, (v, self.tick));
+        if self.map.len() > self.capacity {
+            let (_, key) = self.order.pop_first()?;
+            return self.map.remove(&key).map(|(value, _)| value);
+        }
+        None
+    }
+
+    fn remove<Q>(&mut self, k: &Q) -> Option<T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (value, tick) = self.map.remove(k)?;
+        self.order.remove(&tick);
+        Some(value)
+    }
+}
+
+#[derive(Clone)]
+pub struct SyncLRU<K, T> {
+    inner: Arc<Mutex<Shared<K, T>>>,
+}
+
+impl<K: Clone + Eq + Hash + Send, T: Clone + Send> SyncLRU<K, T> {
+    pub fn new() -> Self {
+        SyncLRU::with_capacity(10)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        SyncLRU {
+            inner: Arc::new(Mutex::new(Shared {
+                capacity,
+                tick: 0,
+                map: HashMap::new(),
+                order: BTreeMap::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared<K, T>> {
+        self.inner.lock().unwrap()
+    }
+
+    pub fn get<Q>(&self, k: &Q) -> Option<T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock().touch(k).cloned()
+    }
+
+    pub fn put(&self, k: K, v: T) -> Option<T> {
+        self.lock().put(k, v)
+    }
+
+    pub fn remove<Q>(&self, k: &Q) -> Option<T>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock().remove(k)
+    }
+
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock().map.contains_key(k)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().map.is_empty()
+    }
+}
+
+impl<K: Clone + Eq + Hash + Send, T: Clone + Send> Default for SyncLRU<K, T> {
+    fn default() -> Self {
+        SyncLRU::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn works_builds_sync_lru() {
+        let lru = SyncLRU::with_capacity(2);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        assert_eq!(lru.get(&1), Some("foo"));
+
+        lru.put(3, "fizz");
+        assert!(!lru.contains_key(&2));
+        assert_eq!(lru.remove(&1), Some("foo"));
+        assert_eq!(lru.len(), 1);
+    }
+
+    #[test]
+    fn works_evicts_least_recently_used() {
+        let lru = SyncLRU::with_capacity(2);
+        lru.put(1, "foo");
+        lru.put(2, "bar");
+        assert_eq!(lru.get(&1), Some("foo"));
+
+        assert_eq!(lru.put(3, "fizz"), Some("bar"));
+        assert_eq!(lru.put(1, "buzz"), Some("foo"));
+        assert!(lru.contains_key(&3));
+        assert_eq!(lru.len(), 2);
+
+        let empty = SyncLRU::with_capacity(0);
+        assert_eq!(empty.put(1, "foo"), None);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn works_builds_default() {
+        let lru: SyncLRU<i32, i32> = Default::default();
+        assert!(lru.is_empty());
+
+        lru.put(1, 10);
+        assert_eq!(lru.get(&1), Some(10));
+    }
+
+    #[test]
+    fn works_shares_across_threads() {
+        let lru = SyncLRU::with_capacity(4);
+        let writer = lru.clone();
+
+        thread::spawn(move || {
+            writer.put(1, "foo");
+            writer.put(2, "bar");
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(lru.get(&1), Some("foo"));
+        assert_eq!(lru.get(&2), Some("bar"));
+        assert_eq!(lru.len(), 2);
+    }
+
+    #[test]
+    fn works_under_concurrent_load() {
+        let lru = SyncLRU::with_capacity(16);
+        let handles: Vec<_> = (0..4)
+            .map(|t| {
+                let lru = lru.clone();
+                thread::spawn(move || {
+                    for i in 0..200 {
+                        let k = (t * 200 + i) % 32;
+                        lru.put(k, k * 2);
+                        if let Some(v) = lru.get(&k) {
+                            assert_eq!(v, k * 2);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(lru.len(), 16);
+        for k in 0..32 {
+            if let Some(v) = lru.get(&k) {
+                assert_eq!(v, k * 2);
+            }
+        }
+    }
+}